@@ -0,0 +1,507 @@
+//! EEPROM emulation on NOR flash, for the few settings that have to survive power cycles
+//!
+//! Flash can only be erased a whole sector at a time, and programming can only clear bits. So
+//! values aren't overwritten in place. Each write appends a record to a log in the active
+//! sector, and the latest committed record for a slot is its value. When the active sector fills
+//! up, the latest records are copied into the other sector, which then takes over.
+//!
+//! Every step is safe to interrupt by power loss:
+//!
+//! - A record's commit byte is programmed after the rest of it, so a torn record is skipped.
+//! - A sector's header is programmed after the records are copied into it, so a torn copy is
+//!   ignored and the old sector stays active.
+//! - The old sector is only erased once the new one has its header. If both have headers, the
+//!   newer generation wins.
+//!
+//! Nothing here touches hardware. [`Flash`] is implemented for the real thing by the firmware.
+
+#![no_std]
+
+/// Two sectors of NOR flash, erased to `0xff`
+pub trait Flash {
+    /// Bytes in each sector, the smallest region that can be erased
+    const SECTOR_LEN: usize;
+
+    /// Read from `offset` bytes into the first sector
+    fn read(&self, offset: usize, buffer: &mut [u8]);
+
+    /// Erase sector 0 or 1
+    fn erase(&mut self, sector: usize);
+
+    /// Program `data` at `offset` bytes into the first sector. The bytes it lands on are erased,
+    /// or already hold `data`.
+    fn program(&mut self, offset: usize, data: &[u8]);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Error {
+    /// Values are up to [`MAX_LEN`] bytes
+    TooLong,
+
+    /// The latest values of every slot don't leave room for this one
+    Full,
+
+    /// Slot `0xff` marks erased flash, so it can't be written
+    ReservedSlot,
+}
+
+/// Longest value a slot can hold
+pub const MAX_LEN: usize = 0xfe;
+
+/// Marks a sector holding a log, followed by its generation
+const MAGIC: [u8; 2] = [0xee, 0x01];
+
+const HEADER_LEN: usize = 4;
+
+/// Erased flash, and so the end of the log
+const ERASED: u8 = 0xff;
+
+/// Programmed into a record's last byte once the rest of it is written
+const COMMITTED: u8 = 0x00;
+
+/// Slot and length before a record's value, commit byte after
+const RECORD_OVERHEAD: usize = 3;
+
+/// One record in a sector's log
+#[derive(Clone, Copy)]
+struct Record {
+    offset: usize,
+    slot: u8,
+    len: usize,
+    committed: bool,
+}
+
+impl Record {
+    fn value_offset(&self) -> usize {
+        self.offset + 2
+    }
+
+    fn end(&self) -> usize {
+        self.offset + RECORD_OVERHEAD + self.len
+    }
+}
+
+pub struct Eeprom<F> {
+    flash: F,
+    active: usize,
+    generation: u16,
+
+    /// Where the next record goes, or `None` if a torn record left the end of the log unusable
+    end: Option<usize>,
+}
+
+impl<F: Flash> Eeprom<F> {
+    /// Find the active sector, and start a fresh log if there isn't one, like on new flash
+    pub fn new(flash: F) -> Self {
+        let mut eeprom = Eeprom {
+            flash,
+            active: 0,
+            generation: 0,
+            end: None,
+        };
+
+        let generations = [eeprom.header(0), eeprom.header(1)];
+        match generations {
+            [Some(first), Some(second)] => {
+                // A copy was interrupted before the old sector was erased. Generations wrap, so
+                // the newer one is whichever is one ahead.
+                let active = if second == first.wrapping_add(1) {
+                    1
+                } else {
+                    0
+                };
+                eeprom.mount(active, generations[active].unwrap());
+            }
+            [Some(generation), None] => eeprom.mount(0, generation),
+            [None, Some(generation)] => eeprom.mount(1, generation),
+            [None, None] => {
+                eeprom.flash.erase(0);
+                eeprom.write_header(0, 0);
+                eeprom.mount(0, 0);
+            }
+        }
+        eeprom
+    }
+
+    fn mount(&mut self, active: usize, generation: u16) {
+        self.active = active;
+        self.generation = generation;
+        self.end = self.log_end();
+        // Finish off an interrupted copy, so the next one has an erased sector to go to
+        if self.header(1 - active).is_some() || !self.is_erased(1 - active) {
+            self.flash.erase(1 - active);
+        }
+    }
+
+    /// Read `slot`'s latest value into `buffer`. Returns `false`, leaving `buffer` alone, if the
+    /// slot has never been written or its value is a different length.
+    pub fn read(&self, slot: u8, buffer: &mut [u8]) -> bool {
+        match self.latest(self.active, slot) {
+            Some(record) if record.len == buffer.len() => {
+                self.flash
+                    .read(self.address(self.active, record.value_offset()), buffer);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Set `slot`'s value. Writing the value it already has leaves the flash alone.
+    pub fn write(&mut self, slot: u8, value: &[u8]) -> Result<(), Error> {
+        if value.len() > MAX_LEN {
+            return Err(Error::TooLong);
+        }
+        if slot == ERASED {
+            return Err(Error::ReservedSlot);
+        }
+        if self.holds(slot, value) {
+            return Ok(());
+        }
+
+        let needed = RECORD_OVERHEAD + value.len();
+        let fits = |end: Option<usize>| end.is_some_and(|end| end + needed <= F::SECTOR_LEN);
+        if !fits(self.end) {
+            self.compact()?;
+            if !fits(self.end) {
+                return Err(Error::Full);
+            }
+        }
+
+        let offset = self.end.unwrap();
+        let address = self.address(self.active, offset);
+        self.flash.program(address, &[slot, value.len() as u8]);
+        self.flash.program(address + 2, value);
+        self.flash.program(address + 2 + value.len(), &[COMMITTED]);
+        self.end = Some(offset + needed);
+        Ok(())
+    }
+
+    /// Whether `slot` already holds `value`
+    fn holds(&self, slot: u8, value: &[u8]) -> bool {
+        let record = match self.latest(self.active, slot) {
+            Some(record) if record.len == value.len() => record,
+            _ => return false,
+        };
+        let mut byte = [0];
+        (0..value.len()).all(|i| {
+            self.flash.read(
+                self.address(self.active, record.value_offset() + i),
+                &mut byte,
+            );
+            byte[0] == value[i]
+        })
+    }
+
+    /// Copy the latest value of every slot into the other sector, and switch to it
+    fn compact(&mut self) -> Result<(), Error> {
+        let (from, to) = (self.active, 1 - self.active);
+        let mut end = HEADER_LEN;
+        let mut offset = HEADER_LEN;
+        while let Some(record) = self.record(from, offset) {
+            offset = record.end();
+            let latest = self
+                .latest(from, record.slot)
+                .is_some_and(|latest| latest.offset == record.offset);
+            if !record.committed || !latest {
+                continue;
+            }
+
+            let len = RECORD_OVERHEAD + record.len;
+            if end + len > F::SECTOR_LEN {
+                return Err(Error::Full);
+            }
+            let mut chunk = [0; RECORD_OVERHEAD + MAX_LEN];
+            self.flash
+                .read(self.address(from, record.offset), &mut chunk[..len]);
+            self.flash.program(self.address(to, end), &chunk[..len]);
+            end += len;
+        }
+
+        let generation = self.generation.wrapping_add(1);
+        self.write_header(to, generation);
+        self.flash.erase(from);
+        self.active = to;
+        self.generation = generation;
+        self.end = Some(end);
+        Ok(())
+    }
+
+    /// The last committed record for `slot` in `sector`
+    fn latest(&self, sector: usize, slot: u8) -> Option<Record> {
+        let mut latest = None;
+        let mut offset = HEADER_LEN;
+        while let Some(record) = self.record(sector, offset) {
+            if record.committed && record.slot == slot {
+                latest = Some(record);
+            }
+            offset = record.end();
+        }
+        latest
+    }
+
+    /// The record at `offset` in `sector`, if there's one that fits in the sector
+    fn record(&self, sector: usize, offset: usize) -> Option<Record> {
+        if offset + RECORD_OVERHEAD > F::SECTOR_LEN {
+            return None;
+        }
+        let mut head = [0; 2];
+        self.flash.read(self.address(sector, offset), &mut head);
+        let [slot, len] = head;
+        let len = len as usize;
+        if slot == ERASED || len > MAX_LEN || offset + RECORD_OVERHEAD + len > F::SECTOR_LEN {
+            return None;
+        }
+        let mut commit = [0];
+        self.flash
+            .read(self.address(sector, offset + 2 + len), &mut commit);
+        Some(Record {
+            offset,
+            slot,
+            len,
+            committed: commit[0] == COMMITTED,
+        })
+    }
+
+    /// Where the next record goes in the active sector, or `None` if the log ends in a record
+    /// that was torn before its length was written
+    fn log_end(&self) -> Option<usize> {
+        let mut offset = HEADER_LEN;
+        while let Some(record) = self.record(self.active, offset) {
+            offset = record.end();
+        }
+        let mut head = [ERASED; 2];
+        let available = F::SECTOR_LEN.saturating_sub(offset).min(2);
+        self.flash
+            .read(self.address(self.active, offset), &mut head[..available]);
+        if head[..available].iter().all(|&byte| byte == ERASED) {
+            Some(offset)
+        } else {
+            None
+        }
+    }
+
+    fn header(&self, sector: usize) -> Option<u16> {
+        let mut header = [0; HEADER_LEN];
+        self.flash.read(self.address(sector, 0), &mut header);
+        if header[..2] == MAGIC {
+            Some(u16::from_le_bytes([header[2], header[3]]))
+        } else {
+            None
+        }
+    }
+
+    fn write_header(&mut self, sector: usize, generation: u16) {
+        let mut header = [0; HEADER_LEN];
+        header[..2].copy_from_slice(&MAGIC);
+        header[2..].copy_from_slice(&generation.to_le_bytes());
+        self.flash.program(self.address(sector, 0), &header);
+    }
+
+    fn is_erased(&self, sector: usize) -> bool {
+        let mut chunk = [0; 64];
+        (0..F::SECTOR_LEN).step_by(chunk.len()).all(|offset| {
+            let len = chunk.len().min(F::SECTOR_LEN - offset);
+            self.flash
+                .read(self.address(sector, offset), &mut chunk[..len]);
+            chunk[..len].iter().all(|&byte| byte == ERASED)
+        })
+    }
+
+    fn address(&self, sector: usize, offset: usize) -> usize {
+        sector * F::SECTOR_LEN + offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tm035035::calibration::{AxisRange, Calibration};
+
+    const SECTOR_LEN: usize = 64;
+
+    /// NOR flash in RAM, checking that nothing is programmed without being erased first
+    struct RamFlash {
+        bytes: [u8; 2 * SECTOR_LEN],
+
+        /// Programs left before the power goes out, if it does
+        power: Option<usize>,
+    }
+
+    impl RamFlash {
+        fn new() -> Self {
+            RamFlash {
+                bytes: [ERASED; 2 * SECTOR_LEN],
+                power: None,
+            }
+        }
+
+        fn powered(&mut self) -> bool {
+            match &mut self.power {
+                Some(0) => false,
+                Some(left) => {
+                    *left -= 1;
+                    true
+                }
+                None => true,
+            }
+        }
+    }
+
+    impl Flash for &mut RamFlash {
+        const SECTOR_LEN: usize = SECTOR_LEN;
+
+        fn read(&self, offset: usize, buffer: &mut [u8]) {
+            buffer.copy_from_slice(&self.bytes[offset..offset + buffer.len()]);
+        }
+
+        fn erase(&mut self, sector: usize) {
+            if self.powered() {
+                self.bytes[sector * SECTOR_LEN..(sector + 1) * SECTOR_LEN].fill(ERASED);
+            }
+        }
+
+        fn program(&mut self, offset: usize, data: &[u8]) {
+            if !self.powered() {
+                return;
+            }
+            for (byte, &new) in self.bytes[offset..offset + data.len()].iter_mut().zip(data) {
+                assert!(
+                    *byte == ERASED || *byte == new,
+                    "Programmed without erasing"
+                );
+                *byte = new;
+            }
+        }
+    }
+
+    fn calibration(min: u16) -> Calibration {
+        let axis = AxisRange {
+            min,
+            max: min + 1600,
+            center: min + 800,
+        };
+        Calibration { x: axis, y: axis }
+    }
+
+    fn read_calibration<F: Flash>(eeprom: &Eeprom<F>, slot: u8) -> Option<Calibration> {
+        let mut bytes = [0; 12];
+        if eeprom.read(slot, &mut bytes) {
+            Calibration::from_bytes(&bytes)
+        } else {
+            None
+        }
+    }
+
+    #[test]
+    fn calibration_survives_power_cycle() {
+        let mut flash = RamFlash::new();
+        let mut eeprom = Eeprom::new(&mut flash);
+        assert!(read_calibration(&eeprom, 0).is_none());
+        eeprom.write(0, &calibration(100).to_bytes()).unwrap();
+        eeprom.write(1, &calibration(200).to_bytes()).unwrap();
+
+        let eeprom = Eeprom::new(&mut flash);
+        assert_eq!(read_calibration(&eeprom, 0).unwrap().x.min, 100);
+        let restored = read_calibration(&eeprom, 1).unwrap();
+        assert_eq!(
+            (restored.y.min, restored.y.max, restored.y.center),
+            (200, 1800, 1000)
+        );
+    }
+
+    #[test]
+    fn latest_write_wins_across_compaction() {
+        let mut flash = RamFlash::new();
+        let mut eeprom = Eeprom::new(&mut flash);
+        eeprom.write(1, &calibration(50).to_bytes()).unwrap();
+        // Each record is 15 bytes, so this fills the 64 byte sectors several times over
+        for min in 0..20 {
+            eeprom.write(0, &calibration(min).to_bytes()).unwrap();
+        }
+
+        let eeprom = Eeprom::new(&mut flash);
+        assert_eq!(read_calibration(&eeprom, 0).unwrap().x.min, 19);
+        assert_eq!(read_calibration(&eeprom, 1).unwrap().x.min, 50);
+    }
+
+    #[test]
+    fn rewriting_same_value_leaves_flash_alone() {
+        let mut flash = RamFlash::new();
+        let mut eeprom = Eeprom::new(&mut flash);
+        eeprom.write(0, &calibration(100).to_bytes()).unwrap();
+        let end = eeprom.end;
+        eeprom.write(0, &calibration(100).to_bytes()).unwrap();
+        assert_eq!(eeprom.end, end);
+    }
+
+    #[test]
+    fn torn_record_keeps_old_value() {
+        let mut flash = RamFlash::new();
+        let mut eeprom = Eeprom::new(&mut flash);
+        eeprom.write(0, &calibration(100).to_bytes()).unwrap();
+
+        // Power goes out after the slot, length and value, before the commit byte
+        eeprom.flash.power = Some(2);
+        eeprom.write(0, &calibration(300).to_bytes()).unwrap();
+        flash.power = None;
+
+        let mut eeprom = Eeprom::new(&mut flash);
+        assert_eq!(read_calibration(&eeprom, 0).unwrap().x.min, 100);
+        eeprom.write(0, &calibration(400).to_bytes()).unwrap();
+        let eeprom = Eeprom::new(&mut flash);
+        assert_eq!(read_calibration(&eeprom, 0).unwrap().x.min, 400);
+    }
+
+    #[test]
+    fn interrupted_compaction_keeps_old_sector() {
+        let mut flash = RamFlash::new();
+        let mut eeprom = Eeprom::new(&mut flash);
+        for min in 0..4 {
+            eeprom.write(0, &calibration(min).to_bytes()).unwrap();
+        }
+
+        // The sector is full, so this copies. Power goes out with the copy made, before the
+        // new sector's header.
+        eeprom.flash.power = Some(1);
+        eeprom.write(0, &calibration(500).to_bytes()).unwrap();
+        flash.power = None;
+
+        let mut eeprom = Eeprom::new(&mut flash);
+        assert_eq!(read_calibration(&eeprom, 0).unwrap().x.min, 3);
+        eeprom.write(0, &calibration(600).to_bytes()).unwrap();
+        let eeprom = Eeprom::new(&mut flash);
+        assert_eq!(read_calibration(&eeprom, 0).unwrap().x.min, 600);
+    }
+
+    #[test]
+    fn interrupted_erase_picks_newer_sector() {
+        let mut flash = RamFlash::new();
+        let mut eeprom = Eeprom::new(&mut flash);
+        for min in 0..4 {
+            eeprom.write(0, &calibration(min).to_bytes()).unwrap();
+        }
+
+        // Power goes out after the new sector's header, before the old sector is erased
+        eeprom.flash.power = Some(2);
+        eeprom.write(0, &calibration(500).to_bytes()).unwrap();
+        flash.power = None;
+
+        let eeprom = Eeprom::new(&mut flash);
+        assert_eq!(read_calibration(&eeprom, 0).unwrap().x.min, 3);
+        assert_eq!(eeprom.active, 1);
+    }
+
+    #[test]
+    fn wrong_length_or_too_long() {
+        let mut flash = RamFlash::new();
+        let mut eeprom = Eeprom::new(&mut flash);
+        eeprom.write(0, &[1, 2, 3]).unwrap();
+        assert!(!eeprom.read(0, &mut [0; 2]));
+        assert_eq!(eeprom.write(0, &[0; MAX_LEN + 1]), Err(Error::TooLong));
+        assert_eq!(eeprom.write(ERASED, &[0]), Err(Error::ReservedSlot));
+        // Bigger than a whole sector
+        assert_eq!(eeprom.write(0, &[0; SECTOR_LEN]), Err(Error::Full));
+        assert!(eeprom.read(0, &mut [0; 3]));
+    }
+}
@@ -0,0 +1,514 @@
+//! The DualShock 4's USB protocol
+//!
+//! Input report 0x01 carries the buttons, sticks, IMU and two touchpad contacts. Hosts read the
+//! IMU calibration, MAC and firmware version from feature reports before trusting the rest, and
+//! send rumble and light bar changes in output report 0x05.
+//!
+//! ```
+//! use gamepad::ds4::{self, button, InputReport, Touch};
+//!
+//! let report = InputReport {
+//!     buttons: button::CROSS | button::PS,
+//!     hat: 8,
+//!     touches: [
+//!         Touch { id: 5, active: true, x: 1919, y: 941 },
+//!         Touch::default(),
+//!     ],
+//!     ..Default::default()
+//! };
+//! let bytes = report.to_bytes();
+//! assert_eq!(bytes[5..8], [0x28, 0x00, 0x01]);
+//! assert_eq!(bytes[35..39], [0x05, 0x7f, 0xd7, 0x3a]);
+//! assert_eq!(bytes[39] & 0x80, 0x80);
+//!
+//! let mut calibration = [0; 64];
+//! assert_eq!(ds4::feature_report(0x02, [0; 6], &mut calibration), Some(37));
+//! ```
+
+use crate::frame::Frame;
+use crate::{hat, trigger, trigger_pressed};
+
+pub const VID: u16 = 0x054c;
+pub const PID: u16 = 0x05c4;
+
+pub const REPORT_LEN: usize = 64;
+
+/// The input report, with real usages for the sticks, hat, buttons and triggers so plain HID
+/// drivers can use it too, then the output and feature reports as vendor data
+pub const REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x05, // Usage (Gamepad)
+    0xa1, 0x01, // Collection (Application)
+    0x85, 0x01, //   Report ID (1)
+    //
+    0x09, 0x30, //   Usage (X)
+    0x09, 0x31, //   Usage (Y)
+    0x09, 0x32, //   Usage (Z)
+    0x09, 0x35, //   Usage (Rz)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x26, 0xff, 0x00, //   Logical Maximum (255)
+    0x75, 0x08, //   Report Size (8)
+    0x95, 0x04, //   Report Count (4)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    //
+    0x09, 0x39, //   Usage (Hat Switch)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x07, //   Logical Maximum (7)
+    0x35, 0x00, //   Physical Minimum (0)
+    0x46, 0x3b, 0x01, //   Physical Maximum (315)
+    0x65, 0x14, //   Unit (Degrees)
+    0x75, 0x04, //   Report Size (4)
+    0x95, 0x01, //   Report Count (1)
+    0x81, 0x42, //   Input (Data, Variable, Absolute, Null State)
+    0x65, 0x00, //   Unit (None)
+    0x45, 0x00, //   Physical Maximum (0)
+    //
+    0x05, 0x09, //   Usage Page (Button)
+    0x19, 0x01, //   Usage Minimum (1)
+    0x29, 0x0e, //   Usage Maximum (14)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x0e, //   Report Count (14)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    //
+    0x06, 0x00, 0xff, //   Usage Page (Vendor Defined 0xFF00)
+    0x09, 0x20, //   Usage (0x20), report counter
+    0x25, 0x3f, //   Logical Maximum (63)
+    0x75, 0x06, //   Report Size (6)
+    0x95, 0x01, //   Report Count (1)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    //
+    0x05, 0x01, //   Usage Page (Generic Desktop)
+    0x09, 0x33, //   Usage (Rx)
+    0x09, 0x34, //   Usage (Ry)
+    0x26, 0xff, 0x00, //   Logical Maximum (255)
+    0x75, 0x08, //   Report Size (8)
+    0x95, 0x02, //   Report Count (2)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    //
+    0x06, 0x00, 0xff, //   Usage Page (Vendor Defined 0xFF00)
+    0x09, 0x21, //   Usage (0x21), timestamp, IMU and touchpad
+    0x95, 0x36, //   Report Count (54)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    //
+    0x85, 0x05, //   Report ID (5)
+    0x09, 0x22, //   Usage (0x22), rumble and light bar
+    0x95, 0x1f, //   Report Count (31)
+    0x91, 0x02, //   Output (Data, Variable, Absolute)
+    //
+    0x85, 0x02, //   Report ID (2)
+    0x09, 0x24, //   Usage (0x24), IMU calibration
+    0x95, 0x24, //   Report Count (36)
+    0xb1, 0x02, //   Feature (Data, Variable, Absolute)
+    //
+    0x85, 0x12, //   Report ID (0x12)
+    0x09, 0x25, //   Usage (0x25), pairing info
+    0x95, 0x0f, //   Report Count (15)
+    0xb1, 0x02, //   Feature (Data, Variable, Absolute)
+    //
+    0x85, 0xa3, //   Report ID (0xa3)
+    0x09, 0x26, //   Usage (0x26), firmware info
+    0x95, 0x30, //   Report Count (48)
+    0xb1, 0x02, //   Feature (Data, Variable, Absolute)
+    0xc0, // End Collection
+];
+
+/// Button bits: the high nibble of the hat byte, the next byte, then the low bits of the byte
+/// after, as they appear in the report
+pub mod button {
+    pub const SQUARE: u32 = 0x0_0010;
+    pub const CROSS: u32 = 0x0_0020;
+    pub const CIRCLE: u32 = 0x0_0040;
+    pub const TRIANGLE: u32 = 0x0_0080;
+    pub const L1: u32 = 0x0_0100;
+    pub const R1: u32 = 0x0_0200;
+    pub const L2: u32 = 0x0_0400;
+    pub const R2: u32 = 0x0_0800;
+    pub const SHARE: u32 = 0x0_1000;
+    pub const OPTIONS: u32 = 0x0_2000;
+    pub const L3: u32 = 0x0_4000;
+    pub const R3: u32 = 0x0_8000;
+    pub const PS: u32 = 0x1_0000;
+    pub const TOUCHPAD: u32 = 0x2_0000;
+}
+
+/// IMU counts per g and per dps. The calibration feature report tells hosts the same.
+pub const ACCEL_PER_G: f32 = 8192.0;
+pub const GYRO_PER_DPS: f32 = 16.0;
+
+/// Touchpad coordinates run from 0 up to these, exclusive
+pub const TOUCHPAD_WIDTH: u16 = 1920;
+pub const TOUCHPAD_HEIGHT: u16 = 942;
+
+/// Timestamps count in units of 16/3 µs
+pub fn timestamp(micros: u64) -> u16 {
+    (micros * 3 / 16) as u16
+}
+
+/// Scale a stick axis from -1..1, with +Y pointing down
+pub fn stick(value: f32) -> u8 {
+    (128.0 + value.clamp(-1.0, 1.0) * 127.0) as u8
+}
+
+/// One touchpad contact
+#[derive(Clone, Copy, Default)]
+pub struct Touch {
+    /// Goes up by one for each new contact, wrapping at 128
+    pub id: u8,
+    pub active: bool,
+    pub x: u16,
+    pub y: u16,
+}
+
+impl Touch {
+    fn to_bytes(self) -> [u8; 4] {
+        let inactive = if self.active { 0x00 } else { 0x80 };
+        [
+            inactive | (self.id & 0x7f),
+            self.x as u8,
+            ((self.x >> 8) & 0x0f) as u8 | ((self.y & 0x0f) << 4) as u8,
+            (self.y >> 4) as u8,
+        ]
+    }
+}
+
+/// One input report
+#[derive(Clone, Copy, Default)]
+pub struct InputReport {
+    /// As from [`stick`]
+    pub left_stick: (u8, u8),
+    pub right_stick: (u8, u8),
+
+    /// As from [`hat`](crate::hat)
+    pub hat: u8,
+
+    /// Bits from [`button`]
+    pub buttons: u32,
+
+    pub left_trigger: u8,
+    pub right_trigger: u8,
+
+    /// Goes up by one for each report, wrapping at 64
+    pub counter: u8,
+
+    /// As from [`timestamp`]
+    pub timestamp: u16,
+
+    /// Pitch, yaw and roll rates, in units of 1 / `GYRO_PER_DPS` dps
+    pub gyro: (i16, i16, i16),
+
+    /// In units of 1 / `ACCEL_PER_G` g
+    pub accel: (i16, i16, i16),
+
+    pub touches: [Touch; 2],
+}
+
+impl InputReport {
+    pub fn to_bytes(&self) -> [u8; REPORT_LEN] {
+        let mut bytes = [0; REPORT_LEN];
+        bytes[0] = 0x01;
+        bytes[1] = self.left_stick.0;
+        bytes[2] = self.left_stick.1;
+        bytes[3] = self.right_stick.0;
+        bytes[4] = self.right_stick.1;
+        bytes[5] = (self.hat & 0x0f) | (self.buttons & 0xf0) as u8;
+        bytes[6] = (self.buttons >> 8) as u8;
+        bytes[7] = ((self.buttons >> 16) & 0x03) as u8 | (self.counter << 2);
+        bytes[8] = self.left_trigger;
+        bytes[9] = self.right_trigger;
+        bytes[10..12].copy_from_slice(&self.timestamp.to_le_bytes());
+        let motion = [
+            self.gyro.0,
+            self.gyro.1,
+            self.gyro.2,
+            self.accel.0,
+            self.accel.1,
+            self.accel.2,
+        ];
+        for (i, value) in motion.iter().enumerate() {
+            bytes[13 + i * 2..15 + i * 2].copy_from_slice(&value.to_le_bytes());
+        }
+        // Cable connected, fully charged
+        bytes[30] = 0x1b;
+        // One touchpad packet, with two contacts
+        bytes[33] = 1;
+        bytes[34] = (self.timestamp >> 8) as u8;
+        bytes[35..39].copy_from_slice(&self.touches[0].to_bytes());
+        bytes[39..43].copy_from_slice(&self.touches[1].to_bytes());
+        bytes
+    }
+}
+
+/// IMU calibration: no bias, ±540 dps across ±8640 counts, and ±1 g across ±8192 counts
+const CALIBRATION: [u8; 37] = [
+    0x02, // Report ID
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Gyroscope pitch, yaw and roll bias
+    0xc0, 0x21, 0x40, 0xde, // Gyroscope pitch plus, minus
+    0xc0, 0x21, 0x40, 0xde, // Gyroscope yaw plus, minus
+    0xc0, 0x21, 0x40, 0xde, // Gyroscope roll plus, minus
+    0x1c, 0x02, 0x1c, 0x02, // Gyroscope speed plus, minus
+    0x00, 0x20, 0x00, 0xe0, // Accelerometer X plus, minus
+    0x00, 0x20, 0x00, 0xe0, // Accelerometer Y plus, minus
+    0x00, 0x20, 0x00, 0xe0, // Accelerometer Z plus, minus
+    0x00, 0x00,
+];
+
+/// Build date and time, then hardware and firmware versions
+const FIRMWARE_INFO: [u8; 49] = [
+    0xa3, // Report ID
+    b'S', b'e', b'p', b' ', b'2', b'1', b' ', b'2', b'0', b'1', b'8', 0, 0, 0, 0, 0, //
+    b'0', b'4', b':', b'5', b'0', b':', b'5', b'1', 0, 0, 0, 0, 0, 0, 0, 0, //
+    0x01, 0x00, 0x31, 0x03, 0x00, 0x00, 0x00, 0x49, 0x00, 0x05, 0x00, 0x00, 0x80, 0x03, 0x00, 0x00,
+];
+
+/// Fill in feature report `id` for a controller with address `mac`. Returns its length, or
+/// `None` for reports this doesn't know and buffers too short to hold them.
+pub fn feature_report(id: u8, mac: [u8; 6], report: &mut [u8]) -> Option<usize> {
+    match id {
+        0x02 => {
+            report
+                .get_mut(..CALIBRATION.len())?
+                .copy_from_slice(&CALIBRATION);
+            Some(CALIBRATION.len())
+        }
+        // Our address, least significant byte first, then the host we're paired with: none
+        0x12 => {
+            let report = report.get_mut(..16)?;
+            report.fill(0);
+            report[0] = 0x12;
+            for (slot, &byte) in report[1..7].iter_mut().zip(mac.iter().rev()) {
+                *slot = byte;
+            }
+            report[7..10].copy_from_slice(&[0x08, 0x25, 0x00]);
+            Some(16)
+        }
+        0xa3 => {
+            report
+                .get_mut(..FIRMWARE_INFO.len())?
+                .copy_from_slice(&FIRMWARE_INFO);
+            Some(FIRMWARE_INFO.len())
+        }
+        _ => None,
+    }
+}
+
+/// Output report 0x05, from the host
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Output {
+    /// Strong and weak motor speeds
+    pub rumble: (u8, u8),
+
+    /// Light bar colour
+    pub light: (u8, u8, u8),
+}
+
+impl Output {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        match *bytes {
+            [0x05, _, _, _, weak, strong, red, green, blue, ..] => Some(Output {
+                rumble: (strong, weak),
+                light: (red, green, blue),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The mapping from frames onto input reports. Buttons map by position, so the south quad button
+/// is cross. The right pad is the touchpad, stretched from the unit disk over the whole
+/// rectangle, and clicks it.
+#[derive(Default)]
+pub struct FrameMapping {
+    counter: u8,
+    touch: Touch,
+}
+
+impl FrameMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn report(&mut self, frame: &Frame) -> InputReport {
+        let buttons = [
+            (button::CROSS, frame.right_quad_s),
+            (button::CIRCLE, frame.right_quad_e),
+            (button::SQUARE, frame.right_quad_w),
+            (button::TRIANGLE, frame.right_quad_n),
+            (button::L1, frame.left_bumper),
+            (button::R1, frame.right_bumper),
+            (button::L2, trigger_pressed(frame.left_trigger)),
+            (button::R2, trigger_pressed(frame.right_trigger)),
+            (button::SHARE, frame.select),
+            (button::OPTIONS, frame.start),
+            (button::L3, frame.left_pad_click),
+            (button::PS, frame.home),
+            (button::TOUCHPAD, frame.right_pad_click),
+        ];
+
+        // Each new contact gets the next ID
+        if frame.right_pad_touched && !self.touch.active {
+            self.touch.id = (self.touch.id + 1) & 0x7f;
+        }
+        self.touch.active = frame.right_pad_touched;
+        if frame.right_pad_touched {
+            let scale = |value: f32, size: u16| {
+                ((value.clamp(-1.0, 1.0) + 1.0) / 2.0 * (size - 1) as f32) as u16
+            };
+            self.touch.x = scale(frame.right_pad_x, TOUCHPAD_WIDTH);
+            self.touch.y = scale(frame.right_pad_y, TOUCHPAD_HEIGHT);
+        }
+
+        let imu = |value: f32, per_unit: f32| (value * per_unit) as i16;
+        InputReport {
+            left_stick: (stick(frame.left_stick_x), stick(frame.left_stick_y)),
+            right_stick: (stick(frame.right_stick_x), stick(frame.right_stick_y)),
+            hat: hat(
+                frame.left_quad_n,
+                frame.left_quad_e,
+                frame.left_quad_s,
+                frame.left_quad_w,
+            ),
+            buttons: buttons
+                .iter()
+                .filter(|&&(_, pressed)| pressed)
+                .fold(0, |bits, &(bit, _)| bits | bit),
+            left_trigger: trigger(frame.left_trigger),
+            right_trigger: trigger(frame.right_trigger),
+            counter: self.counter,
+            timestamp: timestamp(frame.time.as_micros() as u64),
+            gyro: (
+                imu(frame.gyro_x, GYRO_PER_DPS),
+                imu(frame.gyro_y, GYRO_PER_DPS),
+                imu(frame.gyro_z, GYRO_PER_DPS),
+            ),
+            accel: (
+                imu(frame.accel_x, ACCEL_PER_G),
+                imu(frame.accel_y, ACCEL_PER_G),
+                imu(frame.accel_z, ACCEL_PER_G),
+            ),
+            touches: [self.touch, Touch::default()],
+        }
+    }
+
+    /// The last report from `report` was sent
+    pub fn sent(&mut self) {
+        self.counter = (self.counter + 1) & 0x3f;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touched(x: f32, y: f32) -> Frame {
+        Frame {
+            right_pad_x: x,
+            right_pad_y: y,
+            right_pad_touched: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn touch_ids_count_contacts() {
+        let mut mapping = FrameMapping::new();
+        assert!(!mapping.report(&Frame::default()).touches[0].active);
+
+        let touch = mapping.report(&touched(0.0, 0.0)).touches[0];
+        assert!(touch.active);
+        assert_eq!(touch.id, 1);
+        // Still the same contact
+        assert_eq!(mapping.report(&touched(0.5, 0.0)).touches[0].id, 1);
+
+        let touch = mapping.report(&Frame::default()).touches[0];
+        assert!(!touch.active);
+        assert_eq!(touch.id, 1);
+        assert_eq!(mapping.report(&touched(0.0, 0.0)).touches[0].id, 2);
+    }
+
+    #[test]
+    fn touch_ids_wrap() {
+        let mut mapping = FrameMapping::new();
+        for _ in 0..126 {
+            mapping.report(&touched(0.0, 0.0));
+            mapping.report(&Frame::default());
+        }
+        assert_eq!(mapping.report(&touched(0.0, 0.0)).touches[0].id, 127);
+        mapping.report(&Frame::default());
+        assert_eq!(mapping.report(&touched(0.0, 0.0)).touches[0].id, 0);
+    }
+
+    #[test]
+    fn touches_stretch_over_the_touchpad() {
+        let mut mapping = FrameMapping::new();
+        let position = |mapping: &mut FrameMapping, x, y| {
+            let touch = mapping.report(&touched(x, y)).touches[0];
+            (touch.x, touch.y)
+        };
+        assert_eq!(position(&mut mapping, -1.0, -1.0), (0, 0));
+        assert_eq!(position(&mut mapping, 1.0, 1.0), (1919, 941));
+        assert_eq!(position(&mut mapping, 0.0, 0.0), (959, 470));
+        // The disk's edge reaches the middle of each side
+        assert_eq!(position(&mut mapping, 1.0, 0.0), (1919, 470));
+        assert_eq!(position(&mut mapping, 0.0, -1.0), (959, 0));
+        // Past the edge stays on the touchpad
+        assert_eq!(position(&mut mapping, -1.5, 1.5), (0, 941));
+    }
+
+    #[test]
+    fn lifted_touch_keeps_its_position() {
+        let mut mapping = FrameMapping::new();
+        mapping.report(&touched(1.0, 1.0));
+        let touch = mapping.report(&Frame::default()).touches[0];
+        assert_eq!((touch.x, touch.y), (1919, 941));
+    }
+
+    #[test]
+    fn counter_wraps() {
+        let mut mapping = FrameMapping::new();
+        for _ in 0..63 {
+            mapping.sent();
+        }
+        assert_eq!(mapping.report(&Frame::default()).counter, 63);
+        mapping.sent();
+        assert_eq!(mapping.report(&Frame::default()).counter, 0);
+    }
+
+    #[test]
+    fn feature_report_lengths() {
+        let mac = [0x01, 0x02, 0x03, 0x04, 0x05, 0x06];
+        let mut report = [0xaa; REPORT_LEN];
+        assert_eq!(feature_report(0x02, mac, &mut report), Some(37));
+        assert_eq!(report[..37], CALIBRATION);
+        assert_eq!(report[37], 0xaa);
+
+        let mut report = [0xaa; REPORT_LEN];
+        assert_eq!(feature_report(0x12, mac, &mut report), Some(16));
+        assert_eq!(
+            report[..16],
+            [
+                0x12, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x08, 0x25, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00
+            ]
+        );
+        assert_eq!(report[16], 0xaa);
+
+        let mut report = [0xaa; REPORT_LEN];
+        assert_eq!(feature_report(0xa3, mac, &mut report), Some(49));
+        assert_eq!(report[..49], FIRMWARE_INFO);
+
+        assert_eq!(feature_report(0x81, mac, &mut report), None);
+    }
+
+    #[test]
+    fn feature_report_short_buffers() {
+        let mac = [0; 6];
+        let mut report = [0xaa; 36];
+        assert_eq!(feature_report(0x02, mac, &mut report), None);
+        assert_eq!(feature_report(0xa3, mac, &mut report), None);
+        assert!(report.iter().all(|&byte| byte == 0xaa));
+        assert_eq!(feature_report(0x12, mac, &mut report[..15]), None);
+        assert_eq!(feature_report(0x12, mac, &mut report[..16]), Some(16));
+    }
+}
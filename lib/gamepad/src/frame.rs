@@ -0,0 +1,179 @@
+//! One reading of every input on the controller, which each protocol maps onto its reports
+
+use core::time::Duration;
+
+#[derive(Clone, Default)]
+pub struct Frame {
+    /// When the frame was read, since boot
+    pub time: Duration,
+
+    pub left_quad_n: bool,
+    pub left_quad_e: bool,
+    pub left_quad_s: bool,
+    pub left_quad_w: bool,
+
+    pub right_quad_n: bool,
+    pub right_quad_e: bool,
+    pub right_quad_s: bool,
+    pub right_quad_w: bool,
+
+    pub left_pad_x: f32,
+    pub left_pad_y: f32,
+    pub left_pad_touched: bool,
+    pub left_pad_click: bool,
+
+    pub right_pad_x: f32,
+    pub right_pad_y: f32,
+    pub right_pad_touched: bool,
+    pub right_pad_click: bool,
+
+    pub left_swipe_n: bool,
+    pub left_swipe_e: bool,
+    pub left_swipe_s: bool,
+    pub left_swipe_w: bool,
+
+    pub right_swipe_n: bool,
+    pub right_swipe_e: bool,
+    pub right_swipe_s: bool,
+    pub right_swipe_w: bool,
+
+    pub left_radial_segment: Option<u8>,
+    pub left_radial_center: bool,
+
+    pub right_radial_segment: Option<u8>,
+    pub right_radial_center: bool,
+
+    pub left_tap: bool,
+    pub left_double_tap: bool,
+
+    pub right_tap: bool,
+    pub right_double_tap: bool,
+
+    pub left_stick_x: f32,
+    pub left_stick_y: f32,
+
+    pub right_stick_x: f32,
+    pub right_stick_y: f32,
+
+    pub mouse_x: i16,
+    pub mouse_y: i16,
+
+    pub wheel: i8,
+    pub wheel_up: bool,
+    pub wheel_down: bool,
+
+    pub home: bool,
+
+    pub select: bool,
+    pub start: bool,
+
+    pub left_bumper: bool,
+    pub right_bumper: bool,
+
+    pub left_trigger: f32,
+    pub right_trigger: f32,
+
+    pub left_grip: bool,
+    pub right_grip: bool,
+
+    /// In g
+    pub accel_x: f32,
+    pub accel_y: f32,
+    pub accel_z: f32,
+
+    /// In dps
+    pub gyro_x: f32,
+    pub gyro_y: f32,
+    pub gyro_z: f32,
+
+    pub mag_x: f32,
+    pub mag_y: f32,
+    pub mag_z: f32,
+}
+
+impl core::fmt::Display for Frame {
+    fn fmt(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+        formatter.write_fmt(format_args!("\nTime: {:?}", self.time))?;
+        formatter.write_fmt(format_args!(
+            "\nQuads: left(N {}, E {}, S {}, W {}) right(N {}, E {}, S {} W {})",
+            self.left_quad_n,
+            self.left_quad_e,
+            self.left_quad_s,
+            self.left_quad_w,
+            self.right_quad_n,
+            self.right_quad_e,
+            self.right_quad_s,
+            self.right_quad_w,
+        ))?;
+        formatter.write_fmt(format_args!(
+            "\nPads: left(X {:.02}, Y {:.02}, touched {}, click {}) right(X {:.02}, Y {:.02}, touched {}, click {})",
+            self.left_pad_x,
+            self.left_pad_y,
+            self.left_pad_touched,
+            self.left_pad_click,
+            self.right_pad_x,
+            self.right_pad_y,
+            self.right_pad_touched,
+            self.right_pad_click,
+        ))?;
+        formatter.write_fmt(format_args!(
+            "\nSwipes: left(N {}, E {}, S {}, W {}) right(N {}, E {}, S {}, W {})",
+            self.left_swipe_n,
+            self.left_swipe_e,
+            self.left_swipe_s,
+            self.left_swipe_w,
+            self.right_swipe_n,
+            self.right_swipe_e,
+            self.right_swipe_s,
+            self.right_swipe_w,
+        ))?;
+        formatter.write_fmt(format_args!(
+            "\nRadial: left(segment {:?}, center {}) right(segment {:?}, center {})",
+            self.left_radial_segment,
+            self.left_radial_center,
+            self.right_radial_segment,
+            self.right_radial_center,
+        ))?;
+        formatter.write_fmt(format_args!(
+            "\nTaps: left(tap {}, double {}) right(tap {}, double {})",
+            self.left_tap, self.left_double_tap, self.right_tap, self.right_double_tap,
+        ))?;
+        formatter.write_fmt(format_args!(
+            "\nSticks: left(X {:.02}, Y {:.02}) right(X {:.02}, Y {:.02})",
+            self.left_stick_x, self.left_stick_y, self.right_stick_x, self.right_stick_y,
+        ))?;
+        formatter.write_fmt(format_args!(
+            "\nMouse: X {}, Y {}, wheel {} (up {}, down {})",
+            self.mouse_x, self.mouse_y, self.wheel, self.wheel_up, self.wheel_down,
+        ))?;
+        formatter.write_fmt(format_args!(
+            "\nselect {}, home {}, start {}",
+            self.select, self.home, self.start,
+        ))?;
+        formatter.write_fmt(format_args!(
+            "\nBumpers: left {}, right {}",
+            self.left_bumper, self.right_bumper,
+        ))?;
+        formatter.write_fmt(format_args!(
+            "\nTriggers: left {}, right {}",
+            self.left_trigger, self.right_trigger,
+        ))?;
+        formatter.write_fmt(format_args!(
+            "\nGrips: left {}, right {}",
+            self.left_grip, self.right_grip,
+        ))?;
+        formatter.write_fmt(format_args!(
+            "\nAccel: X {:.02}, Y {:.02}, Z {:.02}",
+            self.accel_x, self.accel_y, self.accel_z
+        ))?;
+        formatter.write_fmt(format_args!(
+            "\nGyro: X {:.01}, Y {:.01}, Z {:.01}",
+            self.gyro_x, self.gyro_y, self.gyro_z
+        ))?;
+        formatter.write_fmt(format_args!(
+            "\nMag: X {}, Y {}, Z {}",
+            self.mag_x, self.mag_y, self.mag_z
+        ))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+//! A standard HID gamepad, which every OS drives without anything extra installed
+
+/// 48 buttons, a hat switch, sticks and pads as 16-bit axes, 8-bit triggers, and the IMU as 16-bit
+/// acceleration and angular velocity
+pub const REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x05, // Usage (Gamepad)
+    0xa1, 0x01, // Collection (Application)
+    //
+    0x05, 0x09, //   Usage Page (Button)
+    0x19, 0x01, //   Usage Minimum (1)
+    0x29, 0x30, //   Usage Maximum (48)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x30, //   Report Count (48)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    //
+    0x05, 0x01, //   Usage Page (Generic Desktop)
+    0x09, 0x39, //   Usage (Hat Switch)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x07, //   Logical Maximum (7)
+    0x35, 0x00, //   Physical Minimum (0)
+    0x46, 0x3b, 0x01, //   Physical Maximum (315)
+    0x65, 0x14, //   Unit (Degrees)
+    0x75, 0x04, //   Report Size (4)
+    0x95, 0x01, //   Report Count (1)
+    0x81, 0x42, //   Input (Data, Variable, Absolute, Null State)
+    0x65, 0x00, //   Unit (None)
+    0x45, 0x00, //   Physical Maximum (0)
+    0x81, 0x03, //   Input (Constant), 4 bits of padding
+    //
+    0x09, 0x30, //   Usage (X), left stick
+    0x09, 0x31, //   Usage (Y)
+    0x09, 0x33, //   Usage (Rx), right stick
+    0x09, 0x34, //   Usage (Ry)
+    0x09, 0x40, //   Usage (Vx), left pad
+    0x09, 0x41, //   Usage (Vy)
+    0x09, 0x43, //   Usage (Vbrx), right pad
+    0x09, 0x44, //   Usage (Vbry)
+    0x16, 0x01, 0x80, //   Logical Minimum (-32767)
+    0x26, 0xff, 0x7f, //   Logical Maximum (32767)
+    0x75, 0x10, //   Report Size (16)
+    0x95, 0x08, //   Report Count (8)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    //
+    0x09, 0x32, //   Usage (Z), left trigger
+    0x09, 0x35, //   Usage (Rz), right trigger
+    0x15, 0x00, //   Logical Minimum (0)
+    0x26, 0xff, 0x00, //   Logical Maximum (255)
+    0x75, 0x08, //   Report Size (8)
+    0x95, 0x02, //   Report Count (2)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    //
+    0x05, 0x20, //   Usage Page (Sensors)
+    0x09, 0x73, //   Usage (Accelerometer 3D)
+    0xa1, 0x00, //   Collection (Physical)
+    0x0a, 0x53, 0x04, //     Usage (Acceleration Axis X)
+    0x0a, 0x54, 0x04, //     Usage (Acceleration Axis Y)
+    0x0a, 0x55, 0x04, //     Usage (Acceleration Axis Z)
+    0x16, 0x01, 0x80, //     Logical Minimum (-32767)
+    0x26, 0xff, 0x7f, //     Logical Maximum (32767)
+    0x75, 0x10, //     Report Size (16)
+    0x95, 0x03, //     Report Count (3)
+    0x81, 0x02, //     Input (Data, Variable, Absolute)
+    0xc0, //   End Collection
+    //
+    0x09, 0x76, //   Usage (Gyrometer 3D)
+    0xa1, 0x00, //   Collection (Physical)
+    0x0a, 0x57, 0x04, //     Usage (Angular Velocity X Axis)
+    0x0a, 0x58, 0x04, //     Usage (Angular Velocity Y Axis)
+    0x0a, 0x59, 0x04, //     Usage (Angular Velocity Z Axis)
+    0x75, 0x10, //     Report Size (16)
+    0x95, 0x03, //     Report Count (3)
+    0x81, 0x02, //     Input (Data, Variable, Absolute)
+    0xc0, //   End Collection
+    //
+    0xc0, // End Collection
+];
+
+pub const REPORT_LEN: usize = 37;
+
+/// IMU counts per g and per dps, enough for ±8 g and ±2000 dps
+pub const ACCEL_PER_G: f32 = 4096.0;
+pub const GYRO_PER_DPS: f32 = 16.0;
+
+/// One input report. Axes are -32767..32767, with +Y pointing down.
+#[derive(Clone, Copy, Default)]
+pub struct GamepadReport {
+    /// Button 1 is bit 0. Only the low 48 bits are reported.
+    pub buttons: u64,
+
+    /// As from [`hat`](crate::hat)
+    pub hat: u8,
+
+    pub left_stick: (i16, i16),
+    pub right_stick: (i16, i16),
+    pub left_pad: (i16, i16),
+    pub right_pad: (i16, i16),
+
+    pub left_trigger: u8,
+    pub right_trigger: u8,
+
+    /// In units of 1 / `ACCEL_PER_G` g
+    pub accel: (i16, i16, i16),
+
+    /// In units of 1 / `GYRO_PER_DPS` dps
+    pub gyro: (i16, i16, i16),
+}
+
+impl GamepadReport {
+    /// Press or release button `button`, counting from 1 like the descriptor does
+    pub fn set_button(&mut self, button: u8, pressed: bool) {
+        let bit = 1 << (button - 1);
+        if pressed {
+            self.buttons |= bit;
+        } else {
+            self.buttons &= !bit;
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; REPORT_LEN] {
+        let mut bytes = [0; REPORT_LEN];
+        bytes[0..6].copy_from_slice(&self.buttons.to_le_bytes()[0..6]);
+        bytes[6] = self.hat & 0x0f;
+        let axes = [
+            self.left_stick,
+            self.right_stick,
+            self.left_pad,
+            self.right_pad,
+        ];
+        for (i, (x, y)) in axes.iter().enumerate() {
+            bytes[7 + i * 4..9 + i * 4].copy_from_slice(&x.to_le_bytes());
+            bytes[9 + i * 4..11 + i * 4].copy_from_slice(&y.to_le_bytes());
+        }
+        bytes[23] = self.left_trigger;
+        bytes[24] = self.right_trigger;
+        let imu = [self.accel, self.gyro];
+        for (i, (x, y, z)) in imu.iter().enumerate() {
+            bytes[25 + i * 6..27 + i * 6].copy_from_slice(&x.to_le_bytes());
+            bytes[27 + i * 6..29 + i * 6].copy_from_slice(&y.to_le_bytes());
+            bytes[29 + i * 6..31 + i * 6].copy_from_slice(&z.to_le_bytes());
+        }
+        bytes
+    }
+}
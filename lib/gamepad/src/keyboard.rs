@@ -0,0 +1,148 @@
+//! A HID keyboard and consumer control (media keys) on one interface, told apart by report ID
+//!
+//! ```
+//! use gamepad::keyboard::{consumer, key, ConsumerReport, KeyboardReport};
+//!
+//! let mut report = KeyboardReport::default();
+//! for &k in [key::F13, key::F14, key::F15, key::F16, key::F17, key::F18].iter() {
+//!     assert!(report.press(k));
+//! }
+//! assert!(!report.press(key::F19));
+//! assert_eq!(report.to_bytes(), [0x01, 0x00, 0x00, 0x68, 0x69, 0x6a, 0x6b, 0x6c, 0x6d]);
+//!
+//! let media = ConsumerReport { usage: consumer::PLAY_PAUSE };
+//! assert_eq!(media.to_bytes(), [0x02, 0xcd, 0x00]);
+//! ```
+
+pub const KEYBOARD_REPORT_ID: u8 = 0x01;
+pub const CONSUMER_REPORT_ID: u8 = 0x02;
+
+/// A boot-style keyboard report with LED output, then a single consumer control usage
+pub const REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x06, // Usage (Keyboard)
+    0xa1, 0x01, // Collection (Application)
+    0x85, 0x01, //   Report ID (KEYBOARD_REPORT_ID)
+    //
+    0x05, 0x07, //   Usage Page (Keyboard)
+    0x19, 0xe0, //   Usage Minimum (Left Control)
+    0x29, 0xe7, //   Usage Maximum (Right GUI)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x08, //   Report Count (8)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    0x75, 0x08, //   Report Size (8)
+    0x95, 0x01, //   Report Count (1)
+    0x81, 0x03, //   Input (Constant), reserved byte
+    //
+    0x05, 0x08, //   Usage Page (LEDs)
+    0x19, 0x01, //   Usage Minimum (Num Lock)
+    0x29, 0x05, //   Usage Maximum (Kana)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x05, //   Report Count (5)
+    0x91, 0x02, //   Output (Data, Variable, Absolute)
+    0x75, 0x03, //   Report Size (3)
+    0x95, 0x01, //   Report Count (1)
+    0x91, 0x03, //   Output (Constant), 3 bits of padding
+    //
+    0x05, 0x07, //   Usage Page (Keyboard)
+    0x19, 0x00, //   Usage Minimum (0)
+    0x2a, 0xff, 0x00, //   Usage Maximum (255)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x26, 0xff, 0x00, //   Logical Maximum (255)
+    0x75, 0x08, //   Report Size (8)
+    0x95, 0x06, //   Report Count (6)
+    0x81, 0x00, //   Input (Data, Array)
+    //
+    0xc0, // End Collection
+    //
+    0x05, 0x0c, // Usage Page (Consumer)
+    0x09, 0x01, // Usage (Consumer Control)
+    0xa1, 0x01, // Collection (Application)
+    0x85, 0x02, //   Report ID (CONSUMER_REPORT_ID)
+    0x19, 0x00, //   Usage Minimum (0)
+    0x2a, 0xff, 0x03, //   Usage Maximum (1023)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x26, 0xff, 0x03, //   Logical Maximum (1023)
+    0x75, 0x10, //   Report Size (16)
+    0x95, 0x01, //   Report Count (1)
+    0x81, 0x00, //   Input (Data, Array)
+    0xc0, // End Collection
+];
+
+pub const KEYBOARD_REPORT_LEN: usize = 9;
+pub const CONSUMER_REPORT_LEN: usize = 3;
+
+/// Key usages. F13 to F24 aren't on most keyboards, so nothing else claims them.
+pub mod key {
+    pub const F13: u8 = 0x68;
+    pub const F14: u8 = 0x69;
+    pub const F15: u8 = 0x6a;
+    pub const F16: u8 = 0x6b;
+    pub const F17: u8 = 0x6c;
+    pub const F18: u8 = 0x6d;
+    pub const F19: u8 = 0x6e;
+    pub const F20: u8 = 0x6f;
+    pub const F21: u8 = 0x70;
+    pub const F22: u8 = 0x71;
+    pub const F23: u8 = 0x72;
+    pub const F24: u8 = 0x73;
+}
+
+/// Consumer control usages
+pub mod consumer {
+    pub const NEXT_TRACK: u16 = 0xb5;
+    pub const PREVIOUS_TRACK: u16 = 0xb6;
+    pub const PLAY_PAUSE: u16 = 0xcd;
+    pub const MUTE: u16 = 0xe2;
+    pub const VOLUME_UP: u16 = 0xe9;
+    pub const VOLUME_DOWN: u16 = 0xea;
+}
+
+/// Keys held down, up to six at once
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct KeyboardReport {
+    pub modifiers: u8,
+    pub keys: [u8; 6],
+}
+
+impl KeyboardReport {
+    /// Hold down `key`. Returns false if six keys are already held.
+    pub fn press(&mut self, key: u8) -> bool {
+        match self
+            .keys
+            .iter_mut()
+            .find(|held| **held == 0 || **held == key)
+        {
+            Some(slot) => {
+                *slot = key;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; KEYBOARD_REPORT_LEN] {
+        let mut bytes = [0; KEYBOARD_REPORT_LEN];
+        bytes[0] = KEYBOARD_REPORT_ID;
+        bytes[1] = self.modifiers;
+        bytes[3..].copy_from_slice(&self.keys);
+        bytes
+    }
+}
+
+/// The media key held down, or 0 for none
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct ConsumerReport {
+    pub usage: u16,
+}
+
+impl ConsumerReport {
+    pub fn to_bytes(&self) -> [u8; CONSUMER_REPORT_LEN] {
+        let mut bytes = [0; CONSUMER_REPORT_LEN];
+        bytes[0] = CONSUMER_REPORT_ID;
+        bytes[1..].copy_from_slice(&self.usage.to_le_bytes());
+        bytes
+    }
+}
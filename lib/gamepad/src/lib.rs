@@ -0,0 +1,52 @@
+//! Gamepad protocols: the descriptors and report formats of each USB protocol the controller can
+//! speak, plus the mouse and keyboard that go alongside the HID gamepad, and the mapping from a
+//! [`Frame`](frame::Frame) of controller input onto them. Nothing here depends on a USB stack, so
+//! reports can be encoded and checked on the host.
+
+#![no_std]
+
+pub mod ds4;
+pub mod frame;
+pub mod hid;
+pub mod keyboard;
+pub mod mouse;
+pub mod steam;
+pub mod switch;
+pub mod xinput;
+
+/// Scale an axis from -1..1 to a signed 16-bit value, symmetric around 0
+pub fn axis(value: f32) -> i16 {
+    (value.clamp(-1.0, 1.0) * i16::MAX as f32) as i16
+}
+
+/// Scale a trigger from 0..1 to an 8-bit value
+pub fn trigger(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * u8::MAX as f32) as u8
+}
+
+/// How far a trigger has to be pulled to count as pressed, for [`trigger_pressed`]
+pub const TRIGGER_THRESHOLD: f32 = 0.5;
+
+/// Whether a trigger from 0..1 is pulled far enough to press the digital button that protocols
+/// report alongside the analog value
+pub fn trigger_pressed(value: f32) -> bool {
+    value > TRIGGER_THRESHOLD
+}
+
+/// Hat switch position for four direction buttons: 0 is north, going clockwise in eighths, and 8
+/// is centred. Opposite directions cancel out.
+pub fn hat(n: bool, e: bool, s: bool, w: bool) -> u8 {
+    let x = e as i8 - w as i8;
+    let y = s as i8 - n as i8;
+    match (x, y) {
+        (0, -1) => 0,
+        (1, -1) => 1,
+        (1, 0) => 2,
+        (1, 1) => 3,
+        (0, 1) => 4,
+        (-1, 1) => 5,
+        (-1, 0) => 6,
+        (-1, -1) => 7,
+        _ => 8,
+    }
+}
@@ -0,0 +1,71 @@
+//! A HID mouse, for the pads' mouse and scroll wheel output
+
+/// Three buttons, 16-bit relative X and Y, and a wheel
+pub const REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x02, // Usage (Mouse)
+    0xa1, 0x01, // Collection (Application)
+    0x09, 0x01, //   Usage (Pointer)
+    0xa1, 0x00, //   Collection (Physical)
+    //
+    0x05, 0x09, //     Usage Page (Button)
+    0x19, 0x01, //     Usage Minimum (1)
+    0x29, 0x03, //     Usage Maximum (3)
+    0x15, 0x00, //     Logical Minimum (0)
+    0x25, 0x01, //     Logical Maximum (1)
+    0x75, 0x01, //     Report Size (1)
+    0x95, 0x03, //     Report Count (3)
+    0x81, 0x02, //     Input (Data, Variable, Absolute)
+    0x75, 0x05, //     Report Size (5)
+    0x95, 0x01, //     Report Count (1)
+    0x81, 0x03, //     Input (Constant), 5 bits of padding
+    //
+    0x05, 0x01, //     Usage Page (Generic Desktop)
+    0x09, 0x30, //     Usage (X)
+    0x09, 0x31, //     Usage (Y)
+    0x16, 0x01, 0x80, //     Logical Minimum (-32767)
+    0x26, 0xff, 0x7f, //     Logical Maximum (32767)
+    0x75, 0x10, //     Report Size (16)
+    0x95, 0x02, //     Report Count (2)
+    0x81, 0x06, //     Input (Data, Variable, Relative)
+    //
+    0x09, 0x38, //     Usage (Wheel)
+    0x15, 0x81, //     Logical Minimum (-127)
+    0x25, 0x7f, //     Logical Maximum (127)
+    0x75, 0x08, //     Report Size (8)
+    0x95, 0x01, //     Report Count (1)
+    0x81, 0x06, //     Input (Data, Variable, Relative)
+    //
+    0xc0, //   End Collection
+    0xc0, // End Collection
+];
+
+pub const REPORT_LEN: usize = 6;
+
+pub mod button {
+    pub const LEFT: u8 = 0x01;
+    pub const RIGHT: u8 = 0x02;
+    pub const MIDDLE: u8 = 0x04;
+}
+
+/// One input report. Motion is relative, with +Y pointing down and +wheel scrolling up.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct MouseReport {
+    /// Bits from [`button`]
+    pub buttons: u8,
+
+    pub x: i16,
+    pub y: i16,
+    pub wheel: i8,
+}
+
+impl MouseReport {
+    pub fn to_bytes(&self) -> [u8; REPORT_LEN] {
+        let mut bytes = [0; REPORT_LEN];
+        bytes[0] = self.buttons;
+        bytes[1..3].copy_from_slice(&self.x.to_le_bytes());
+        bytes[3..5].copy_from_slice(&self.y.to_le_bytes());
+        bytes[5] = self.wheel as u8;
+        bytes
+    }
+}
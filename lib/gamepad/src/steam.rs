@@ -0,0 +1,233 @@
+//! The Steam Controller's USB protocol, as the wired controller speaks it
+//!
+//! Steam finds the controller by its vendor interface, the one with feature reports. Every
+//! report is 64 bytes without an ID. Input reports carry both pads, the triggers and the IMU.
+//! Steam sends commands as feature reports, and reads any reply back with GET_REPORT.
+//!
+//! ```
+//! use gamepad::steam::{self, button, Command, HapticPulse, InputReport, Side};
+//!
+//! let report = InputReport {
+//!     buttons: button::A | button::RIGHT_PAD_TOUCH,
+//!     right_pad: (-32767, 32767),
+//!     ..Default::default()
+//! };
+//! let bytes = report.to_bytes();
+//! assert_eq!(bytes[..4], [0x01, 0x00, 0x01, 0x3c]);
+//! assert_eq!(bytes[8..11], [0x80, 0x00, 0x10]);
+//! assert_eq!(bytes[20..24], [0x01, 0x80, 0xff, 0x7f]);
+//!
+//! let pulse = [0x8f, 0x07, 0x01, 0xe8, 0x03, 0xe8, 0x03, 0x05, 0x00];
+//! assert_eq!(
+//!     Command::parse(&pulse),
+//!     Some(Command::HapticPulse(HapticPulse { side: Side::Left, on_us: 1000, off_us: 1000, repeat: 5 })),
+//! );
+//!
+//! let mut reply = [0; 64];
+//! steam::reply(&Command::GetSerial, "CFW1", &mut reply);
+//! assert_eq!(reply[..7], [0xae, 0x15, 0x01, b'C', b'F', b'W', b'1']);
+//! ```
+
+pub const VID: u16 = 0x28de;
+pub const PID: u16 = 0x1102;
+
+pub const REPORT_LEN: usize = 64;
+
+pub const REPORT_DESCRIPTOR: &[u8] = &[
+    0x06, 0x00, 0xff, // Usage Page (Vendor Defined 0xFF00)
+    0x09, 0x01, // Usage (1)
+    0xa1, 0x01, // Collection (Application)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x26, 0xff, 0x00, //   Logical Maximum (255)
+    0x75, 0x08, //   Report Size (8)
+    0x95, 0x40, //   Report Count (64)
+    0x09, 0x01, //   Usage (1)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    0x09, 0x01, //   Usage (1)
+    0xb1, 0x02, //   Feature (Data, Variable, Absolute)
+    0xc0, // End Collection
+];
+
+/// Button bits, over the three button bytes as they appear in the report
+pub mod button {
+    pub const RIGHT_TRIGGER: u32 = 0x00_0001;
+    pub const LEFT_TRIGGER: u32 = 0x00_0002;
+    pub const RIGHT_BUMPER: u32 = 0x00_0004;
+    pub const LEFT_BUMPER: u32 = 0x00_0008;
+    pub const Y: u32 = 0x00_0010;
+    pub const B: u32 = 0x00_0020;
+    pub const X: u32 = 0x00_0040;
+    pub const A: u32 = 0x00_0080;
+
+    pub const DPAD_UP: u32 = 0x00_0100;
+    pub const DPAD_RIGHT: u32 = 0x00_0200;
+    pub const DPAD_LEFT: u32 = 0x00_0400;
+    pub const DPAD_DOWN: u32 = 0x00_0800;
+    pub const BACK: u32 = 0x00_1000;
+    pub const STEAM: u32 = 0x00_2000;
+    pub const START: u32 = 0x00_4000;
+    pub const LEFT_GRIP: u32 = 0x00_8000;
+
+    pub const RIGHT_GRIP: u32 = 0x01_0000;
+    pub const LEFT_PAD_CLICK: u32 = 0x02_0000;
+    pub const RIGHT_PAD_CLICK: u32 = 0x04_0000;
+    /// The left pad coordinates are the pad's, not the stick's
+    pub const LEFT_PAD_TOUCH: u32 = 0x08_0000;
+    pub const RIGHT_PAD_TOUCH: u32 = 0x10_0000;
+    pub const STICK_CLICK: u32 = 0x40_0000;
+}
+
+/// IMU counts per g and per dps
+pub const ACCEL_PER_G: f32 = 16384.0;
+pub const GYRO_PER_DPS: f32 = 16.0;
+
+/// One input report. Pads are -32767..32767, with +Y pointing up.
+#[derive(Clone, Copy, Default)]
+pub struct InputReport {
+    /// Goes up by one for each report
+    pub sequence: u32,
+
+    /// Bits from [`button`]
+    pub buttons: u32,
+
+    pub left_trigger: u8,
+    pub right_trigger: u8,
+
+    pub left_pad: (i16, i16),
+    pub right_pad: (i16, i16),
+
+    /// In units of 1 / `ACCEL_PER_G` g
+    pub accel: (i16, i16, i16),
+
+    /// Pitch, roll and yaw rates, in units of 1 / `GYRO_PER_DPS` dps
+    pub gyro: (i16, i16, i16),
+}
+
+impl InputReport {
+    pub fn to_bytes(&self) -> [u8; REPORT_LEN] {
+        let mut bytes = [0; REPORT_LEN];
+        // Version, input report, length
+        bytes[..4].copy_from_slice(&[0x01, 0x00, 0x01, 0x3c]);
+        bytes[4..8].copy_from_slice(&self.sequence.to_le_bytes());
+        bytes[8..11].copy_from_slice(&self.buttons.to_le_bytes()[..3]);
+        bytes[11] = self.left_trigger;
+        bytes[12] = self.right_trigger;
+        let values = [
+            self.left_pad.0,
+            self.left_pad.1,
+            self.right_pad.0,
+            self.right_pad.1,
+        ];
+        for (i, value) in values.iter().enumerate() {
+            bytes[16 + i * 2..18 + i * 2].copy_from_slice(&value.to_le_bytes());
+        }
+        let motion = [
+            self.accel.0,
+            self.accel.1,
+            self.accel.2,
+            self.gyro.0,
+            self.gyro.1,
+            self.gyro.2,
+            // There's no sensor fusion, so the orientation quaternion stays at identity
+            i16::MAX,
+            0,
+            0,
+            0,
+        ];
+        for (i, value) in motion.iter().enumerate() {
+            bytes[28 + i * 2..30 + i * 2].copy_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Side {
+    Right,
+    Left,
+}
+
+/// A train of pulses for one pad's haptic actuator
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HapticPulse {
+    pub side: Side,
+    pub on_us: u16,
+    pub off_us: u16,
+    pub repeat: u16,
+}
+
+/// A command from Steam, sent as a feature report
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    /// Stop emulating a keyboard and mouse, since Steam is handling input
+    ClearMappings,
+
+    /// Go back to emulating a keyboard and mouse
+    DefaultMappings,
+
+    /// Settings registers, which Steam uses to configure the pads and IMU
+    SetSettings,
+
+    GetAttributes,
+    GetSerial,
+    HapticPulse(HapticPulse),
+
+    /// Anything else, by ID
+    Other(u8),
+}
+
+impl Command {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+        let command = match *bytes.first()? {
+            0x81 => Command::ClearMappings,
+            0x85 | 0x8e => Command::DefaultMappings,
+            0x87 => Command::SetSettings,
+            0x83 => Command::GetAttributes,
+            0xae => Command::GetSerial,
+            0x8f if bytes.len() >= 9 => Command::HapticPulse(HapticPulse {
+                side: if bytes[2] == 0 {
+                    Side::Right
+                } else {
+                    Side::Left
+                },
+                on_us: u16_at(3),
+                off_us: u16_at(5),
+                repeat: u16_at(7),
+            }),
+            id => Command::Other(id),
+        };
+        Some(command)
+    }
+}
+
+/// Fill in the reply Steam reads back after `command`, for a controller with serial number
+/// `serial`. Commands without a reply echo their ID.
+pub fn reply(command: &Command, serial: &str, reply: &mut [u8; REPORT_LEN]) {
+    reply.fill(0);
+    let id = match command {
+        // Product ID and firmware build time, as tagged 32-bit values
+        Command::GetAttributes => {
+            let attributes: [(u8, u32); 2] = [(0x01, PID as u32), (0x04, 0x5a7c_1b3a)];
+            reply[1] = (attributes.len() * 5) as u8;
+            for (i, (tag, value)) in attributes.iter().enumerate() {
+                reply[2 + i * 5] = *tag;
+                reply[3 + i * 5..7 + i * 5].copy_from_slice(&value.to_le_bytes());
+            }
+            0x83
+        }
+        // The serial number attribute, padded out to 20 bytes
+        Command::GetSerial => {
+            let serial = &serial.as_bytes()[..serial.len().min(20)];
+            reply[1..3].copy_from_slice(&[0x15, 0x01]);
+            reply[3..3 + serial.len()].copy_from_slice(serial);
+            0xae
+        }
+        Command::ClearMappings => 0x81,
+        Command::DefaultMappings => 0x8e,
+        Command::SetSettings => 0x87,
+        Command::HapticPulse(_) => 0x8f,
+        Command::Other(id) => *id,
+    };
+    reply[0] = id;
+}
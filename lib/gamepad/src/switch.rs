@@ -0,0 +1,616 @@
+//! The Nintendo Switch Pro Controller's USB protocol
+//!
+//! The host drives the controller with output reports: 0x80 commands for the USB link itself,
+//! and 0x01 subcommands, which ask for device info, read calibration out of the SPI flash or
+//! switch features on. Replies come back as 0x81 and 0x21 input reports. Once the host has
+//! finished the USB handshake, the controller streams 0x30 reports, each carrying the last three
+//! IMU samples.
+//!
+//! ```
+//! use gamepad::switch::{InputState, ProController};
+//!
+//! let mut controller = ProController::new([0x02, 0x00, 0x00, 0x00, 0x00, 0x01]);
+//! let input = InputState::default();
+//! assert!(controller.next_report(&input).is_none());
+//!
+//! // Device info
+//! controller.output(&[0x01, 0x00, 0, 0, 0, 0, 0, 0, 0, 0, 0x02]);
+//! let reply = controller.next_report(&input).unwrap();
+//! assert_eq!(reply[0], 0x21);
+//! assert_eq!(reply[13..17], [0x82, 0x02, 0x03, 0x48]);
+//! controller.sent();
+//!
+//! // Factory IMU calibration, from the SPI flash
+//! controller.output(&[0x01, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0x10, 0x20, 0x60, 0x00, 0x00, 0x18]);
+//! let reply = controller.next_report(&input).unwrap();
+//! assert_eq!(reply[13..20], [0x90, 0x10, 0x20, 0x60, 0x00, 0x00, 0x18]);
+//! assert_eq!(reply[26..28], [0x00, 0x40]);
+//! controller.sent();
+//!
+//! // No more USB timeout, so it starts streaming
+//! controller.output(&[0x80, 0x04]);
+//! assert_eq!(controller.next_report(&input).unwrap()[0], 0x30);
+//! ```
+
+use core::time::Duration;
+
+use crate::frame::Frame;
+use crate::trigger_pressed;
+
+pub const VID: u16 = 0x057e;
+pub const PID: u16 = 0x2009;
+
+/// Every report is 63 bytes after its ID
+pub const REPORT_LEN: usize = 64;
+
+/// Vendor-defined reports, one for each ID the protocol uses. Hosts parse the reports
+/// themselves.
+pub const REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x04, // Usage (Joystick)
+    0xa1, 0x01, // Collection (Application)
+    0x06, 0x00, 0xff, //   Usage Page (Vendor Defined 0xFF00)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x26, 0xff, 0x00, //   Logical Maximum (255)
+    0x75, 0x08, //   Report Size (8)
+    0x95, 0x3f, //   Report Count (63)
+    0x85, 0x30, 0x09, 0x30, 0x81, 0x02, //   Report ID 0x30, full input
+    0x85, 0x21, 0x09, 0x21, 0x81, 0x02, //   Report ID 0x21, subcommand reply
+    0x85, 0x81, 0x09, 0x81, 0x81, 0x02, //   Report ID 0x81, USB command reply
+    0x85, 0x01, 0x09, 0x01, 0x91, 0x02, //   Report ID 0x01, rumble and subcommand
+    0x85, 0x10, 0x09, 0x10, 0x91, 0x02, //   Report ID 0x10, rumble only
+    0x85, 0x80, 0x09, 0x80, 0x91, 0x02, //   Report ID 0x80, USB command
+    0x85, 0x82, 0x09, 0x82, 0x91, 0x02, //   Report ID 0x82, UART passthrough
+    0xc0, // End Collection
+];
+
+/// Button bits: the right byte, the shared byte, then the left byte, as they appear in reports
+pub mod button {
+    pub const Y: u32 = 0x00_0001;
+    pub const X: u32 = 0x00_0002;
+    pub const B: u32 = 0x00_0004;
+    pub const A: u32 = 0x00_0008;
+    pub const R: u32 = 0x00_0040;
+    pub const ZR: u32 = 0x00_0080;
+
+    pub const MINUS: u32 = 0x00_0100;
+    pub const PLUS: u32 = 0x00_0200;
+    pub const RIGHT_STICK: u32 = 0x00_0400;
+    pub const LEFT_STICK: u32 = 0x00_0800;
+    pub const HOME: u32 = 0x00_1000;
+    pub const CAPTURE: u32 = 0x00_2000;
+
+    pub const DOWN: u32 = 0x01_0000;
+    pub const UP: u32 = 0x02_0000;
+    pub const RIGHT: u32 = 0x04_0000;
+    pub const LEFT: u32 = 0x08_0000;
+    pub const L: u32 = 0x40_0000;
+    pub const ZL: u32 = 0x80_0000;
+}
+
+/// Stick centre and travel, in 12-bit counts. The factory calibration in the SPI flash says the
+/// same.
+pub const STICK_CENTER: u16 = 0x800;
+pub const STICK_RANGE: u16 = 0x600;
+
+/// IMU counts per g and per dps, at the full scales the factory calibration describes
+pub const ACCEL_PER_G: f32 = 4096.0;
+pub const GYRO_PER_DPS: f32 = 1.0 / 0.070;
+
+/// Scale a stick axis from -1..1, with +Y pointing up
+pub fn stick(value: f32) -> u16 {
+    (STICK_CENTER as f32 + value.clamp(-1.0, 1.0) * STICK_RANGE as f32) as u16
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct ImuSample {
+    pub accel: (i16, i16, i16),
+    pub gyro: (i16, i16, i16),
+}
+
+impl ImuSample {
+    /// From acceleration in g and angular rate in dps
+    pub fn new(accel: (f32, f32, f32), gyro: (f32, f32, f32)) -> Self {
+        let scale = |value: f32, per_unit: f32| (value * per_unit) as i16;
+        ImuSample {
+            accel: (
+                scale(accel.0, ACCEL_PER_G),
+                scale(accel.1, ACCEL_PER_G),
+                scale(accel.2, ACCEL_PER_G),
+            ),
+            gyro: (
+                scale(gyro.0, GYRO_PER_DPS),
+                scale(gyro.1, GYRO_PER_DPS),
+                scale(gyro.2, GYRO_PER_DPS),
+            ),
+        }
+    }
+}
+
+/// Everything the controller reports about its inputs
+#[derive(Clone, Copy, Default)]
+pub struct InputState {
+    /// Bits from [`button`]
+    pub buttons: u32,
+
+    /// As from [`stick`]
+    pub left_stick: (u16, u16),
+    pub right_stick: (u16, u16),
+
+    /// The last three samples, oldest first
+    pub imu: [ImuSample; 3],
+}
+
+/// Two 12-bit values in three bytes
+fn pack12(a: u16, b: u16) -> [u8; 3] {
+    [
+        a as u8,
+        ((a >> 8) & 0x0f) as u8 | ((b & 0x0f) << 4) as u8,
+        (b >> 4) as u8,
+    ]
+}
+
+/// Factory IMU calibration: zero offsets, and the sensitivities `ACCEL_PER_G` and
+/// `GYRO_PER_DPS` work out to
+const IMU_CALIBRATION: [u8; 24] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Accelerometer origin
+    0x00, 0x40, 0x00, 0x40, 0x00, 0x40, // Accelerometer sensitivity, 16384
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Gyroscope origin
+    0x3b, 0x34, 0x3b, 0x34, 0x3b, 0x34, // Gyroscope sensitivity, 13371
+];
+
+/// Factory stick calibration, `STICK_CENTER` ± `STICK_RANGE`. The left stick lists the range
+/// above centre, the centre, then the range below. The right stick lists the centre first.
+const LEFT_STICK_CALIBRATION: [u8; 9] = [0x00, 0x06, 0x60, 0x00, 0x08, 0x80, 0x00, 0x06, 0x60];
+const RIGHT_STICK_CALIBRATION: [u8; 9] = [0x00, 0x08, 0x80, 0x00, 0x06, 0x60, 0x00, 0x06, 0x60];
+
+/// Body, buttons, left grip and right grip colours
+const COLORS: [u8; 12] = [
+    0x32, 0x32, 0x32, 0xff, 0xff, 0xff, 0x32, 0x32, 0x32, 0x32, 0x32, 0x32,
+];
+
+/// Sensor and stick parameters, as a stock controller has them
+const SENSOR_PARAMETERS: [u8; 6] = [0x50, 0xfd, 0x00, 0x00, 0xc6, 0x0f];
+const STICK_PARAMETERS: [u8; 18] = [
+    0x0f, 0x30, 0x61, 0x96, 0x30, 0xf3, 0xd4, 0x14, 0x54, 0x41, 0x15, 0x54, 0xc7, 0x79, 0x9c, 0x33,
+    0x36, 0x63,
+];
+
+/// The parts of the SPI flash hosts read. Everything else is erased, including the user
+/// calibration, so hosts fall back to the factory calibration.
+const FLASH: &[(u32, &[u8])] = &[
+    // Colours are set
+    (0x601b, &[0x01]),
+    (0x6020, &IMU_CALIBRATION),
+    (0x603d, &LEFT_STICK_CALIBRATION),
+    (0x6046, &RIGHT_STICK_CALIBRATION),
+    (0x6050, &COLORS),
+    (0x6080, &SENSOR_PARAMETERS),
+    (0x6086, &STICK_PARAMETERS),
+    (0x6098, &STICK_PARAMETERS),
+];
+
+/// Read `data.len()` bytes of the emulated SPI flash, starting at `address`
+pub fn read_flash(address: u32, data: &mut [u8]) {
+    data.fill(0xff);
+    for &(start, bytes) in FLASH {
+        for (i, &byte) in bytes.iter().enumerate() {
+            let offset = (start + i as u32).wrapping_sub(address) as usize;
+            if let Some(slot) = data.get_mut(offset) {
+                *slot = byte;
+            }
+        }
+    }
+}
+
+/// Longest subcommand reply data: an SPI flash read of 30 bytes, after its address and length
+const MAX_REPLY_DATA: usize = 35;
+
+#[derive(Clone, Copy)]
+enum Reply {
+    /// A 0x81 reply to a USB command
+    Usb {
+        command: u8,
+        data: [u8; 8],
+        len: usize,
+    },
+
+    /// A 0x21 reply to a subcommand
+    Subcommand {
+        ack: u8,
+        id: u8,
+        data: [u8; MAX_REPLY_DATA],
+        len: usize,
+    },
+}
+
+/// The controller's side of the protocol
+pub struct ProController {
+    mac: [u8; 6],
+    timer: u8,
+    streaming: bool,
+    imu_enabled: bool,
+    reply: Option<Reply>,
+}
+
+impl ProController {
+    pub fn new(mac: [u8; 6]) -> Self {
+        ProController {
+            mac,
+            timer: 0,
+            streaming: false,
+            imu_enabled: false,
+            reply: None,
+        }
+    }
+
+    /// Handle an output report from the host, starting with its report ID
+    pub fn output(&mut self, report: &[u8]) {
+        match *report {
+            [0x80, command, ..] => self.usb_command(command),
+            [0x01, ..] if report.len() > 10 => self.subcommand(report[10], &report[11..]),
+            // Rumble, and anything else, is ignored
+            _ => (),
+        }
+    }
+
+    fn usb_command(&mut self, command: u8) {
+        let mut data = [0; 8];
+        let len = match command {
+            // Status: controller type and MAC, least significant byte first
+            0x01 => {
+                data[..2].copy_from_slice(&[0x00, 0x03]);
+                for (slot, &byte) in data[2..].iter_mut().zip(self.mac.iter().rev()) {
+                    *slot = byte;
+                }
+                8
+            }
+            // Handshake and baud rate are just acknowledged
+            0x02 | 0x03 => 0,
+            // No USB timeout, which is when input reports start
+            0x04 => {
+                self.streaming = true;
+                return;
+            }
+            0x05 => {
+                self.streaming = false;
+                return;
+            }
+            _ => return,
+        };
+        self.reply = Some(Reply::Usb { command, data, len });
+    }
+
+    fn subcommand(&mut self, id: u8, args: &[u8]) {
+        let mut data = [0; MAX_REPLY_DATA];
+        let (ack, len) = match id {
+            // Bluetooth pairing: already paired
+            0x01 => {
+                data[0] = 0x03;
+                (0x81, 1)
+            }
+            // Device info: firmware 3.72, Pro Controller, MAC, colours from the SPI flash
+            0x02 => {
+                data[..4].copy_from_slice(&[0x03, 0x48, 0x03, 0x02]);
+                data[4..10].copy_from_slice(&self.mac);
+                data[10..12].copy_from_slice(&[0x01, 0x01]);
+                (0x82, 12)
+            }
+            // Trigger elapsed time
+            0x04 => (0x83, 0),
+            // SPI flash read: the address and length come back ahead of the data
+            0x10 if args.len() >= 5 => {
+                let address = u32::from_le_bytes([args[0], args[1], args[2], args[3]]);
+                let len = (args[4] as usize).min(MAX_REPLY_DATA - 5);
+                data[..5].copy_from_slice(&args[..5]);
+                data[4] = len as u8;
+                read_flash(address, &mut data[5..5 + len]);
+                (0x90, 5 + len)
+            }
+            // NFC/IR MCU configuration
+            0x21 => {
+                data[..8].copy_from_slice(&[0x01, 0x00, 0xff, 0x00, 0x03, 0x00, 0x05, 0x01]);
+                (0xa0, 8)
+            }
+            0x40 => {
+                self.imu_enabled = args.first() == Some(&0x01);
+                (0x80, 0)
+            }
+            // Report mode, lights, vibration, IMU sensitivity and the rest only need an ack
+            _ => (0x80, 0),
+        };
+        self.reply = Some(Reply::Subcommand { ack, id, data, len });
+    }
+
+    /// The next report to send: a reply if one is waiting, otherwise a full input report if the
+    /// host has asked for them. Call [`sent`](Self::sent) once it's gone.
+    pub fn next_report(&self, input: &InputState) -> Option<[u8; REPORT_LEN]> {
+        let mut report = [0; REPORT_LEN];
+        match self.reply {
+            Some(Reply::Usb { command, data, len }) => {
+                report[0] = 0x81;
+                report[1] = command;
+                report[2..2 + len].copy_from_slice(&data[..len]);
+            }
+            Some(Reply::Subcommand { ack, id, data, len }) => {
+                report[0] = 0x21;
+                self.write_input(&mut report, input);
+                report[13] = ack;
+                report[14] = id;
+                report[15..15 + len].copy_from_slice(&data[..len]);
+            }
+            None if self.streaming => {
+                report[0] = 0x30;
+                self.write_input(&mut report, input);
+                if self.imu_enabled {
+                    for (i, sample) in input.imu.iter().enumerate() {
+                        let values = [
+                            sample.accel.0,
+                            sample.accel.1,
+                            sample.accel.2,
+                            sample.gyro.0,
+                            sample.gyro.1,
+                            sample.gyro.2,
+                        ];
+                        for (j, value) in values.iter().enumerate() {
+                            let at = 13 + i * 12 + j * 2;
+                            report[at..at + 2].copy_from_slice(&value.to_le_bytes());
+                        }
+                    }
+                }
+            }
+            None => return None,
+        }
+        Some(report)
+    }
+
+    /// The last report from [`next_report`](Self::next_report) was sent
+    pub fn sent(&mut self) {
+        self.reply = None;
+        self.timer = self.timer.wrapping_add(1);
+    }
+
+    fn write_input(&self, report: &mut [u8; REPORT_LEN], input: &InputState) {
+        report[1] = self.timer;
+        // Full battery, charging, powered over USB
+        report[2] = 0x91;
+        report[3..6].copy_from_slice(&input.buttons.to_le_bytes()[..3]);
+        report[6..9].copy_from_slice(&pack12(input.left_stick.0, input.left_stick.1));
+        report[9..12].copy_from_slice(&pack12(input.right_stick.0, input.right_stick.1));
+        report[12] = 0x80;
+    }
+}
+
+/// Time between the IMU samples in a report, which the Switch integrates over
+pub const IMU_PERIOD: Duration = Duration::from_millis(5);
+
+/// The mapping from frames onto input state. Buttons map by position rather than by name, so the
+/// east quad button is A like on a Switch.
+#[derive(Default)]
+pub struct FrameMapping {
+    input: InputState,
+    imu_due: Duration,
+}
+
+impl FrameMapping {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take in a frame, and return the input state for it. IMU samples are taken from frames
+    /// every [`IMU_PERIOD`], with the newest repeated if frames come slower than that, and stay
+    /// in the following states whether or not this one is reported.
+    pub fn update(&mut self, frame: &Frame) -> &InputState {
+        let buttons = [
+            (button::A, frame.right_quad_e),
+            (button::B, frame.right_quad_s),
+            (button::X, frame.right_quad_n),
+            (button::Y, frame.right_quad_w),
+            (button::UP, frame.left_quad_n),
+            (button::DOWN, frame.left_quad_s),
+            (button::LEFT, frame.left_quad_w),
+            (button::RIGHT, frame.left_quad_e),
+            (button::L, frame.left_bumper),
+            (button::R, frame.right_bumper),
+            (button::ZL, trigger_pressed(frame.left_trigger)),
+            (button::ZR, trigger_pressed(frame.right_trigger)),
+            (button::MINUS, frame.select),
+            (button::PLUS, frame.start),
+            (button::HOME, frame.home),
+            (button::LEFT_STICK, frame.left_pad_click),
+            (button::RIGHT_STICK, frame.right_pad_click),
+        ];
+
+        let sample = ImuSample::new(
+            (frame.accel_x, frame.accel_y, frame.accel_z),
+            (frame.gyro_x, frame.gyro_y, frame.gyro_z),
+        );
+        let mut imu = self.input.imu;
+        for _ in 0..imu.len() {
+            if frame.time < self.imu_due {
+                break;
+            }
+            imu.rotate_left(1);
+            imu[imu.len() - 1] = sample;
+            self.imu_due += IMU_PERIOD;
+        }
+        // Samples missed for longer than a report covers are gone, rather than caught up on
+        if frame.time >= self.imu_due {
+            self.imu_due = frame.time + IMU_PERIOD;
+        }
+
+        self.input = InputState {
+            buttons: buttons
+                .iter()
+                .filter(|&&(_, pressed)| pressed)
+                .fold(0, |bits, &(bit, _)| bits | bit),
+            // The Switch's +Y is up
+            left_stick: (stick(frame.left_stick_x), stick(-frame.left_stick_y)),
+            right_stick: (stick(frame.right_stick_x), stick(-frame.right_stick_y)),
+            imu,
+        };
+        &self.input
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAC: [u8; 6] = [0x02, 0x00, 0x00, 0x00, 0x00, 0x01];
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    /// A 0x01 output report carrying subcommand `id`, with no rumble
+    fn subcommand(id: u8, args: &[u8]) -> [u8; 49] {
+        let mut report = [0; 49];
+        report[0] = 0x01;
+        report[10] = id;
+        report[11..11 + args.len()].copy_from_slice(args);
+        report
+    }
+
+    fn streaming() -> ProController {
+        let mut controller = ProController::new(MAC);
+        controller.output(&[0x80, 0x04]);
+        controller
+    }
+
+    /// A frame at `time`, with the accelerometer's X reading `time` in ms, in g
+    fn imu_frame(time: u64) -> Frame {
+        Frame {
+            time: ms(time),
+            accel_x: time as f32 / ACCEL_PER_G,
+            ..Default::default()
+        }
+    }
+
+    fn accel_x(input: &InputState) -> [i16; 3] {
+        [
+            input.imu[0].accel.0,
+            input.imu[1].accel.0,
+            input.imu[2].accel.0,
+        ]
+    }
+
+    #[test]
+    fn flash_read_across_entries() {
+        let mut controller = ProController::new(MAC);
+        controller.output(&subcommand(0x10, &[0x4c, 0x60, 0x00, 0x00, 0x08]));
+        let reply = controller.next_report(&InputState::default()).unwrap();
+        assert_eq!(reply[13..20], [0x90, 0x10, 0x4c, 0x60, 0x00, 0x00, 0x08]);
+        // The end of the right stick calibration, an erased byte, then the colours
+        assert_eq!(
+            reply[20..28],
+            [0x00, 0x06, 0x60, 0xff, 0x32, 0x32, 0x32, 0xff]
+        );
+        assert_eq!(reply[28], 0x00);
+    }
+
+    #[test]
+    fn long_flash_read_is_cut_short() {
+        let mut controller = ProController::new(MAC);
+        controller.output(&subcommand(0x10, &[0x20, 0x60, 0x00, 0x00, 0x40]));
+        let reply = controller.next_report(&InputState::default()).unwrap();
+        // 30 bytes is all a reply has room for, and the length says so
+        assert_eq!(reply[15..20], [0x20, 0x60, 0x00, 0x00, 30]);
+        assert_eq!(reply[20..44], IMU_CALIBRATION);
+        // Erased, then the start of the left stick calibration
+        assert_eq!(reply[44..50], [0xff, 0xff, 0xff, 0xff, 0xff, 0x00]);
+        assert!(reply[50..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn short_subcommand_reports() {
+        let mut controller = ProController::new(MAC);
+        // Too short to hold a subcommand ID
+        controller.output(&[0x01, 0x00, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert!(controller.next_report(&InputState::default()).is_none());
+
+        // A flash read without its address and length is only acknowledged
+        controller.output(&[0x01, 0x00, 0, 0, 0, 0, 0, 0, 0, 0, 0x10, 0x20]);
+        let reply = controller.next_report(&InputState::default()).unwrap();
+        assert_eq!(reply[13..16], [0x80, 0x10, 0x00]);
+    }
+
+    #[test]
+    fn imu_only_when_enabled() {
+        let mut input = InputState::default();
+        for (i, sample) in input.imu.iter_mut().enumerate() {
+            let i = i as i16;
+            *sample = ImuSample {
+                accel: (i + 1, -2, 0x0300),
+                gyro: (4, 5, -6),
+            };
+        }
+
+        let mut controller = streaming();
+        let report = controller.next_report(&input).unwrap();
+        assert_eq!(report[0], 0x30);
+        assert!(report[13..].iter().all(|&byte| byte == 0));
+
+        controller.output(&subcommand(0x40, &[0x01]));
+        controller.sent();
+        let report = controller.next_report(&input).unwrap();
+        assert_eq!(report[0], 0x30);
+        assert_eq!(
+            report[13..25],
+            [0x01, 0x00, 0xfe, 0xff, 0x00, 0x03, 0x04, 0x00, 0x05, 0x00, 0xfa, 0xff]
+        );
+        // Oldest first
+        assert_eq!(report[25..27], [0x02, 0x00]);
+        assert_eq!(report[37..39], [0x03, 0x00]);
+        assert!(report[49..].iter().all(|&byte| byte == 0));
+
+        controller.output(&subcommand(0x40, &[0x00]));
+        controller.sent();
+        let report = controller.next_report(&input).unwrap();
+        assert!(report[13..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn imu_sampled_every_period() {
+        let mut mapping = FrameMapping::new();
+        assert_eq!(accel_x(mapping.update(&imu_frame(0))), [0, 0, 0]);
+        // Not due yet
+        assert_eq!(accel_x(mapping.update(&imu_frame(3))), [0, 0, 0]);
+        assert_eq!(accel_x(mapping.update(&imu_frame(5))), [0, 0, 5]);
+        assert_eq!(accel_x(mapping.update(&imu_frame(10))), [0, 5, 10]);
+    }
+
+    #[test]
+    fn imu_catches_up_on_slow_frames() {
+        let mut mapping = FrameMapping::new();
+        mapping.update(&imu_frame(0));
+        // Two samples were due by now, so the newest is repeated
+        assert_eq!(accel_x(mapping.update(&imu_frame(11))), [0, 11, 11]);
+        // And the next is due on the original schedule
+        assert_eq!(accel_x(mapping.update(&imu_frame(15))), [11, 11, 15]);
+    }
+
+    #[test]
+    fn imu_drops_long_gaps() {
+        let mut mapping = FrameMapping::new();
+        mapping.update(&imu_frame(0));
+        // Only a report's worth of samples is caught up on
+        assert_eq!(accel_x(mapping.update(&imu_frame(100))), [100, 100, 100]);
+        // Then sampling restarts from here, rather than 85 ms behind
+        assert_eq!(accel_x(mapping.update(&imu_frame(104))), [100, 100, 100]);
+        assert_eq!(accel_x(mapping.update(&imu_frame(105))), [100, 100, 105]);
+    }
+
+    #[test]
+    fn buttons_by_position() {
+        let frame = Frame {
+            right_quad_e: true,
+            right_quad_s: true,
+            left_quad_n: true,
+            right_trigger: 0.6,
+            ..Default::default()
+        };
+        let input = FrameMapping::new().update(&frame).buttons;
+        assert_eq!(input, button::A | button::B | button::UP | button::ZR);
+    }
+}
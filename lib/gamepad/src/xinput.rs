@@ -0,0 +1,309 @@
+//! The Xbox 360 wired controller's protocol, which XInput games on Windows expect
+//!
+//! It isn't HID: the controller has a vendor-specific interface, with its own descriptor and
+//! fixed report layouts. XInput only has two sticks, so the pads drive them through their
+//! joystick modes.
+//!
+//! ```
+//! use gamepad::xinput::{button, InputReport, Output};
+//!
+//! let report = InputReport {
+//!     buttons: button::A | button::DPAD_UP,
+//!     left_trigger: 255,
+//!     left_stick: (-32767, 32767),
+//!     ..Default::default()
+//! };
+//! let bytes = report.to_bytes();
+//! assert_eq!(bytes[..6], [0x00, 0x14, 0x01, 0x10, 0xff, 0x00]);
+//! assert_eq!(bytes[6..10], [0x01, 0x80, 0xff, 0x7f]);
+//!
+//! let rumble = [0x00, 0x08, 0x00, 0x80, 0x40, 0x00, 0x00, 0x00];
+//! assert_eq!(Output::parse(&rumble), Some(Output::Rumble { left: 0x80, right: 0x40 }));
+//! ```
+
+use crate::frame::Frame;
+use crate::{axis, trigger};
+
+pub const VID: u16 = 0x045e;
+pub const PID: u16 = 0x028e;
+pub const DEVICE_RELEASE: u16 = 0x0114;
+
+/// Class, subclass and protocol of the device, and of the controller interface
+pub const DEVICE_CLASS: (u8, u8, u8) = (0xff, 0xff, 0xff);
+pub const INTERFACE_CLASS: (u8, u8, u8) = (0xff, 0x5d, 0x01);
+
+pub const IN_ENDPOINT: u8 = 0x81;
+pub const OUT_ENDPOINT: u8 = 0x01;
+
+/// The undocumented descriptor that follows the interface descriptor, without its length and
+/// type bytes. It names the endpoints and their report sizes.
+pub const INTERFACE_DESCRIPTOR_TYPE: u8 = 0x21;
+pub const INTERFACE_DESCRIPTOR: &[u8] = &[
+    0x00,
+    0x01,
+    0x01,
+    0x25,
+    IN_ENDPOINT,
+    0x14,
+    0x00,
+    0x00,
+    0x00,
+    0x00,
+    0x13,
+    OUT_ENDPOINT,
+    0x08,
+    0x00,
+    0x00,
+];
+
+pub const REPORT_LEN: usize = 20;
+
+/// Button bits, in the order they appear in the report
+pub mod button {
+    pub const DPAD_UP: u16 = 0x0001;
+    pub const DPAD_DOWN: u16 = 0x0002;
+    pub const DPAD_LEFT: u16 = 0x0004;
+    pub const DPAD_RIGHT: u16 = 0x0008;
+    pub const START: u16 = 0x0010;
+    pub const BACK: u16 = 0x0020;
+    pub const LEFT_THUMB: u16 = 0x0040;
+    pub const RIGHT_THUMB: u16 = 0x0080;
+    pub const LEFT_SHOULDER: u16 = 0x0100;
+    pub const RIGHT_SHOULDER: u16 = 0x0200;
+    pub const GUIDE: u16 = 0x0400;
+    pub const A: u16 = 0x1000;
+    pub const B: u16 = 0x2000;
+    pub const X: u16 = 0x4000;
+    pub const Y: u16 = 0x8000;
+}
+
+/// One input report. Sticks are -32767..32767, with +Y pointing up.
+#[derive(Clone, Copy, Default)]
+pub struct InputReport {
+    /// Bits from [`button`]
+    pub buttons: u16,
+
+    pub left_trigger: u8,
+    pub right_trigger: u8,
+
+    pub left_stick: (i16, i16),
+    pub right_stick: (i16, i16),
+}
+
+impl InputReport {
+    pub fn to_bytes(&self) -> [u8; REPORT_LEN] {
+        let mut bytes = [0; REPORT_LEN];
+        bytes[0] = 0x00;
+        bytes[1] = REPORT_LEN as u8;
+        bytes[2..4].copy_from_slice(&self.buttons.to_le_bytes());
+        bytes[4] = self.left_trigger;
+        bytes[5] = self.right_trigger;
+        bytes[6..8].copy_from_slice(&self.left_stick.0.to_le_bytes());
+        bytes[8..10].copy_from_slice(&self.left_stick.1.to_le_bytes());
+        bytes[10..12].copy_from_slice(&self.right_stick.0.to_le_bytes());
+        bytes[12..14].copy_from_slice(&self.right_stick.1.to_le_bytes());
+        bytes
+    }
+}
+
+/// A message from the host
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Output {
+    /// Motor speeds. The left motor is the big, low-frequency one.
+    Rumble { left: u8, right: u8 },
+
+    /// Ring light animation, 0 to 13
+    Led(u8),
+}
+
+impl Output {
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        match *bytes {
+            [0x00, 0x08, _, left, right, ..] => Some(Output::Rumble { left, right }),
+            [0x01, 0x03, pattern, ..] => Some(Output::Led(pattern)),
+            _ => None,
+        }
+    }
+}
+
+/// Map a frame onto an input report. Buttons map by position, so the south quad button is A.
+pub fn report(frame: &Frame) -> InputReport {
+    let buttons = [
+        (button::DPAD_UP, frame.left_quad_n),
+        (button::DPAD_DOWN, frame.left_quad_s),
+        (button::DPAD_LEFT, frame.left_quad_w),
+        (button::DPAD_RIGHT, frame.left_quad_e),
+        (button::START, frame.start),
+        (button::BACK, frame.select),
+        (button::LEFT_THUMB, frame.left_pad_click),
+        (button::RIGHT_THUMB, frame.right_pad_click),
+        (button::LEFT_SHOULDER, frame.left_bumper),
+        (button::RIGHT_SHOULDER, frame.right_bumper),
+        (button::GUIDE, frame.home),
+        (button::A, frame.right_quad_s),
+        (button::B, frame.right_quad_e),
+        (button::X, frame.right_quad_w),
+        (button::Y, frame.right_quad_n),
+    ];
+
+    InputReport {
+        buttons: buttons
+            .iter()
+            .filter(|&&(_, pressed)| pressed)
+            .fold(0, |bits, &(bit, _)| bits | bit),
+        left_trigger: trigger(frame.left_trigger),
+        right_trigger: trigger(frame.right_trigger),
+        // XInput's +Y is up
+        left_stick: (axis(frame.left_stick_x), axis(-frame.left_stick_y)),
+        right_stick: (axis(frame.right_stick_x), axis(-frame.right_stick_y)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn input_report_layout() {
+        let report = InputReport {
+            buttons: button::Y | button::START,
+            left_trigger: 0x12,
+            right_trigger: 0x34,
+            left_stick: (0x0102, -2),
+            right_stick: (i16::MIN + 1, 0x7f00),
+        };
+        assert_eq!(
+            report.to_bytes(),
+            [
+                0x00, 0x14, 0x10, 0x80, 0x12, 0x34, 0x02, 0x01, 0xfe, 0xff, 0x01, 0x80, 0x00, 0x7f,
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_input_report() {
+        let bytes = InputReport::default().to_bytes();
+        assert_eq!(bytes[..2], [0x00, REPORT_LEN as u8]);
+        assert!(bytes[2..].iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn parse_outputs() {
+        assert_eq!(
+            Output::parse(&[0x00, 0x08, 0x00, 0xff, 0x01, 0x00, 0x00, 0x00]),
+            Some(Output::Rumble {
+                left: 0xff,
+                right: 0x01
+            })
+        );
+        assert_eq!(Output::parse(&[0x01, 0x03, 0x0a]), Some(Output::Led(0x0a)));
+    }
+
+    #[test]
+    fn parse_short_outputs() {
+        assert_eq!(Output::parse(&[]), None);
+        assert_eq!(Output::parse(&[0x00]), None);
+        assert_eq!(Output::parse(&[0x00, 0x08, 0x00, 0xff]), None);
+        assert_eq!(Output::parse(&[0x01, 0x03]), None);
+    }
+
+    #[test]
+    fn parse_malformed_outputs() {
+        // Rumble with the wrong length
+        assert_eq!(Output::parse(&[0x00, 0x03, 0x00, 0xff, 0x01]), None);
+        // LED with the wrong length
+        assert_eq!(Output::parse(&[0x01, 0x08, 0x0a, 0x00, 0x00]), None);
+        // Unknown type
+        assert_eq!(Output::parse(&[0x02, 0x08, 0x00, 0xff, 0x01, 0x00]), None);
+    }
+
+    #[test]
+    fn face_buttons_by_position() {
+        let cases = [
+            (
+                Frame {
+                    right_quad_s: true,
+                    ..Default::default()
+                },
+                button::A,
+            ),
+            (
+                Frame {
+                    right_quad_e: true,
+                    ..Default::default()
+                },
+                button::B,
+            ),
+            (
+                Frame {
+                    right_quad_w: true,
+                    ..Default::default()
+                },
+                button::X,
+            ),
+            (
+                Frame {
+                    right_quad_n: true,
+                    ..Default::default()
+                },
+                button::Y,
+            ),
+        ];
+        for (frame, bit) in &cases {
+            assert_eq!(report(frame).buttons, *bit);
+        }
+    }
+
+    #[test]
+    fn dpad_bits() {
+        let frame = Frame {
+            left_quad_n: true,
+            left_quad_w: true,
+            ..Default::default()
+        };
+        assert_eq!(report(&frame).buttons, 0x0005);
+
+        let frame = Frame {
+            left_quad_s: true,
+            left_quad_e: true,
+            ..Default::default()
+        };
+        assert_eq!(report(&frame).buttons, 0x000a);
+    }
+
+    #[test]
+    fn eight_bit_triggers() {
+        let frame = Frame {
+            left_trigger: 1.0,
+            right_trigger: 0.5,
+            ..Default::default()
+        };
+        let report = report(&frame);
+        assert_eq!((report.left_trigger, report.right_trigger), (255, 127));
+        assert_eq!(report.to_bytes()[4..6], [0xff, 0x7f]);
+
+        let frame = Frame {
+            left_trigger: -0.5,
+            right_trigger: 2.0,
+            ..Default::default()
+        };
+        let report = super::report(&frame);
+        assert_eq!((report.left_trigger, report.right_trigger), (0, 255));
+    }
+
+    #[test]
+    fn stick_y_points_up() {
+        // Frames have +Y pointing down
+        let frame = Frame {
+            left_stick_x: 1.0,
+            left_stick_y: 1.0,
+            right_stick_x: -1.0,
+            right_stick_y: -1.0,
+            ..Default::default()
+        };
+        let report = report(&frame);
+        assert_eq!(report.left_stick, (32767, -32767));
+        assert_eq!(report.right_stick, (-32767, 32767));
+    }
+}
@@ -15,6 +15,13 @@ impl Register for Ctrl1Xl {
     }
 }
 
+impl Ctrl1Xl {
+    /// Acceleration per LSB, in g
+    pub fn sensitivity(&self) -> f32 {
+        self.1.sensitivity()
+    }
+}
+
 /// Output data rate and power mode selection.
 pub enum OdrXl {
     /// Power-down
@@ -102,6 +109,18 @@ impl RegisterSetting for FsXl {
         0b_0000_1100
     }
 }
+
+impl FsXl {
+    /// Acceleration per LSB, in g
+    pub fn sensitivity(&self) -> f32 {
+        match self {
+            Self::TwoG => 0.000_061,
+            Self::FourG => 0.000_122,
+            Self::EightG => 0.000_244,
+            Self::SixteenG => 0.000_488,
+        }
+    }
+}
 /// Anti-aliasing filter bandwidth selection.
 pub enum BwXl {
     /// 50 Hz
@@ -14,6 +14,16 @@ impl Register for Ctrl2G {
     }
 }
 
+impl Ctrl2G {
+    /// Angular rate per LSB, in dps
+    pub fn sensitivity(&self) -> f32 {
+        match self.2 {
+            Fs125::Dps125 => 0.004_375,
+            Fs125::DpsByFsG => self.1.sensitivity(),
+        }
+    }
+}
+
 /// Gyroscope output data rate selection.
 pub enum OdrG {
     /// Power-down
@@ -93,6 +103,18 @@ impl RegisterSetting for FsG {
         0b_0000_1100
     }
 }
+
+impl FsG {
+    /// Angular rate per LSB, in dps
+    pub fn sensitivity(&self) -> f32 {
+        match self {
+            Self::Dps250 => 0.008_75,
+            Self::Dps500 => 0.017_5,
+            Self::Dps1000 => 0.035,
+            Self::Dps2000 => 0.070,
+        }
+    }
+}
 /// Gyroscope full-scale at 125 dps.
 pub enum Fs125 {
     DpsByFsG,
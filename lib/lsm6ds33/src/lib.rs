@@ -18,24 +18,35 @@ pub enum ImuError<S: Transfer<u8>> {
     TransferFailed(S::Error),
 }
 
+/// Readings are in g and dps, scaled for the full scales passed to `try_new`
 pub struct Lsm6ds33<S, C> {
     spi: S,
     cs: C,
+    accel_sensitivity: f32,
+    gyro_sensitivity: f32,
 }
 
 impl Lsm6ds33<!, !> {
+    /// `accel` and `gyro` set each sensor's data rate and full scale
     pub fn try_new<S: Transfer<u8>, C: OutputPin>(
         spi: S,
         cs: C,
+        accel: Ctrl1Xl,
+        gyro: Ctrl2G,
     ) -> Result<Lsm6ds33<S, C>, ImuError<S>> {
-        let mut imu = Lsm6ds33 { spi, cs };
+        let mut imu = Lsm6ds33 {
+            spi,
+            cs,
+            accel_sensitivity: accel.sensitivity(),
+            gyro_sensitivity: gyro.sensitivity(),
+        };
 
         let mut buf = [0x00; 1];
         imu.read_bytes(0x0f, &mut buf)?;
 
         // Initialize configuration registers
-        imu.configure(Ctrl1Xl(OdrXl::DataRate12_5Hz, FsXl::TwoG, BwXl::Bw50Hz))?;
-        imu.configure(Ctrl2G(OdrG::DataRate12_5Hz, FsG::Dps250, Fs125::DpsByFsG))?;
+        imu.configure(accel)?;
+        imu.configure(gyro)?;
         imu.configure(Ctrl4C(
             XlBwScalOdr::ByBwXl,
             SleepG::GyroWake,
@@ -102,16 +113,16 @@ impl<S: Transfer<u8>, C: OutputPin> Lsm6ds33<S, C> {
 
         let gyro = (
             // Gyroscope pitch, roll, yaw
-            i16::from_le_bytes(buf[0..2].try_into().unwrap()).into(),
-            i16::from_le_bytes(buf[2..4].try_into().unwrap()).into(),
-            i16::from_le_bytes(buf[4..6].try_into().unwrap()).into(),
+            i16::from_le_bytes(buf[0..2].try_into().unwrap()) as f32 * self.gyro_sensitivity,
+            i16::from_le_bytes(buf[2..4].try_into().unwrap()) as f32 * self.gyro_sensitivity,
+            i16::from_le_bytes(buf[4..6].try_into().unwrap()) as f32 * self.gyro_sensitivity,
         );
 
         let accel = (
             // Accelerometer X, Y, Z
-            i16::from_le_bytes(buf[6..8].try_into().unwrap()).into(),
-            i16::from_le_bytes(buf[8..10].try_into().unwrap()).into(),
-            i16::from_le_bytes(buf[10..12].try_into().unwrap()).into(),
+            i16::from_le_bytes(buf[6..8].try_into().unwrap()) as f32 * self.accel_sensitivity,
+            i16::from_le_bytes(buf[8..10].try_into().unwrap()) as f32 * self.accel_sensitivity,
+            i16::from_le_bytes(buf[10..12].try_into().unwrap()) as f32 * self.accel_sensitivity,
         );
 
         Ok((accel, gyro))
@@ -0,0 +1,546 @@
+//! A simulated Pinnacle ASIC, as found on the TM035035, for testing the `tm035035` driver on the
+//! host without a trackpad.
+//!
+//! The simulator speaks the register access protocol: `0xa0 | addr` reads followed by `0xfc`
+//! filler bytes, and `0x80 | addr` writes. It models the status flags and the data ready line
+//! the way the driver relies on them. Resets and calibrations complete immediately, unless the
+//! chip is set to stop responding, and scripted samples are loaded one at a time, each time the
+//! driver clears the status flags.
+//!
+//! It's meant to be used from host-side tests, but it isn't a `std` crate. It's a member of the
+//! workspace, and workspace builds default to the firmware's `thumbv7em-none-eabihf` target,
+//! which has no `std`. Needing only `alloc`, for the sample script and the `Rc` shared between
+//! the bus and data ready halves, keeps `cargo build --workspace` working for that target.
+//!
+//! ```
+//! use tm035035::config::feedconfig1::DataMode;
+//! use tm035035::{Touch, Tm035035};
+//! use tm035035_sim::{Delay, Pinnacle, Sample};
+//!
+//! let pinnacle = Pinnacle::new();
+//! pinnacle.play([Sample::Absolute { x: 1919, y: 767, z: 255 }, Sample::Idle]);
+//!
+//! let mut pad = Tm035035::try_new(pinnacle.spi(), pinnacle.data_ready(), DataMode::Absolute, &mut Delay, 10)
+//!     .unwrap_or_else(|_| panic!("Simulated pad didn't respond"));
+//!
+//! match pad.next() {
+//!     Some(Ok(Touch::Touched(x, y, _))) => assert!(x > 0.99 && y.abs() < 0.01),
+//!     _ => panic!("Expected a touch"),
+//! }
+//! assert!(matches!(pad.next(), Some(Ok(Touch::NotTouched))));
+//! assert!(pad.next().is_none());
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use core::cell::RefCell;
+use core::convert::Infallible;
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::spi::Transfer;
+use embedded_hal::digital::v2::InputPin;
+
+const READ_CMD: u8 = 0xa0;
+const WRITE_CMD: u8 = 0x80;
+const FILLER: u8 = 0xfc;
+
+const STATUS1: u8 = 0x02;
+const SYS_CONFIG1: u8 = 0x03;
+const FEED_CONFIG1: u8 = 0x04;
+const CAL_CONFIG1: u8 = 0x07;
+const PACKET_BYTE0: u8 = 0x12;
+const ANY_MEAS_RESULT: u8 = 0x11;
+
+const SW_DR: u8 = 0b_0000_0100;
+const SW_CC: u8 = 0b_0000_1000;
+
+/// One scripted sample, loaded into the packet registers when the driver is ready for it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sample {
+    /// An absolute-mode touch, in raw 12-bit sensor counts
+    Absolute { x: u16, y: u16, z: u8 },
+
+    /// A relative-mode movement, with button bits as in the first packet byte
+    Relative {
+        dx: i16,
+        dy: i16,
+        buttons: u8,
+        wheel: i8,
+    },
+
+    /// A "Z idle" packet, sent after the finger lifts
+    Idle,
+
+    /// An AnyMeas measurement result
+    Measurement(i16),
+}
+
+impl Sample {
+    /// A straight swipe from one absolute position to another in `steps` samples,
+    /// followed by `idle` Z idle packets.
+    pub fn swipe(
+        from: (u16, u16),
+        to: (u16, u16),
+        z: u8,
+        steps: u16,
+        idle: usize,
+    ) -> VecDeque<Self> {
+        let lerp = |a: u16, b: u16, i: u16| {
+            (a as i32 + (b as i32 - a as i32) * i as i32 / (steps.max(2) - 1) as i32) as u16
+        };
+        (0..steps)
+            .map(|i| Sample::Absolute {
+                x: lerp(from.0, to.0, i),
+                y: lerp(from.1, to.1, i),
+                z,
+            })
+            .chain((0..idle).map(|_| Sample::Idle))
+            .collect()
+    }
+}
+
+struct Chip {
+    registers: [u8; 0x20],
+    script: VecDeque<Sample>,
+    responding: bool,
+}
+
+impl Chip {
+    fn new() -> Self {
+        let mut chip = Chip {
+            registers: [0; 0x20],
+            script: VecDeque::new(),
+            responding: true,
+        };
+        chip.power_on();
+        chip
+    }
+
+    fn power_on(&mut self) {
+        self.registers = [0; 0x20];
+        // Firmware ID and version
+        self.registers[0x00] = 0x07;
+        self.registers[0x01] = 0x3a;
+        // Reset complete
+        self.signal(SW_CC | SW_DR);
+    }
+
+    /// Raise status flags, unless the chip has stopped responding
+    fn signal(&mut self, flags: u8) {
+        if self.responding {
+            self.registers[STATUS1 as usize] |= flags;
+        }
+    }
+
+    fn data_ready(&self) -> bool {
+        self.registers[STATUS1 as usize] & (SW_CC | SW_DR) != 0
+    }
+
+    fn feed_enabled(&self) -> bool {
+        self.registers[FEED_CONFIG1 as usize] & 0b_0000_0001 != 0
+    }
+
+    fn any_meas(&self) -> bool {
+        self.registers[SYS_CONFIG1 as usize] & 0b_0000_1000 != 0
+    }
+
+    /// Load the next scripted packet, if the feed is on and the last one was consumed
+    fn sample(&mut self) {
+        if self.data_ready() || !self.feed_enabled() || self.any_meas() {
+            return;
+        }
+        match self.script.front() {
+            // Measurements wait for the driver to start one
+            Some(Sample::Measurement(_)) | None => (),
+            Some(_) => {
+                let sample = self.script.pop_front().unwrap();
+                self.load(sample);
+            }
+        }
+    }
+
+    fn load(&mut self, sample: Sample) {
+        let packet = &mut self.registers[PACKET_BYTE0 as usize..PACKET_BYTE0 as usize + 6];
+        match sample {
+            Sample::Absolute { x, y, z } => packet.copy_from_slice(&[
+                0x00,
+                0x00,
+                x as u8,
+                y as u8,
+                ((x >> 8) & 0x0f) as u8 | (((y >> 8) & 0x0f) << 4) as u8,
+                z,
+            ]),
+            Sample::Relative {
+                dx,
+                dy,
+                buttons,
+                wheel,
+            } => {
+                let sign_x = if dx < 0 { 0x10 } else { 0x00 };
+                let sign_y = if dy < 0 { 0x20 } else { 0x00 };
+                packet.copy_from_slice(&[
+                    0x08 | sign_x | sign_y | (buttons & 0x07),
+                    dx as u8,
+                    dy as u8,
+                    wheel as u8,
+                    0x00,
+                    0x00,
+                ]);
+            }
+            Sample::Idle => packet.fill(0x00),
+            Sample::Measurement(_) => unreachable!(),
+        }
+        self.signal(SW_DR);
+    }
+
+    fn measure(&mut self) {
+        let result = match self.script.front() {
+            Some(&Sample::Measurement(result)) => {
+                self.script.pop_front();
+                result
+            }
+            _ => 0,
+        };
+        let [high, low] = result.to_be_bytes();
+        self.registers[ANY_MEAS_RESULT as usize] = high;
+        self.registers[ANY_MEAS_RESULT as usize + 1] = low;
+        self.signal(SW_DR);
+    }
+
+    fn write(&mut self, addr: u8, byte: u8) {
+        match addr {
+            STATUS1 => {
+                self.registers[STATUS1 as usize] = byte;
+                self.sample();
+            }
+            SYS_CONFIG1 if byte & 0b_0000_0001 != 0 => {
+                let script = core::mem::take(&mut self.script);
+                self.power_on();
+                self.script = script;
+            }
+            SYS_CONFIG1 if byte & 0b_0001_1000 == 0b_0001_1000 => {
+                // The start bit clears itself
+                self.registers[SYS_CONFIG1 as usize] = byte & !0b_0001_0000;
+                self.measure();
+            }
+            CAL_CONFIG1 if !self.any_meas() => {
+                // The calibrate bit clears itself
+                self.registers[CAL_CONFIG1 as usize] = byte & !0b_0000_0001;
+                if byte & 0b_0000_0001 != 0 {
+                    self.signal(SW_CC);
+                }
+            }
+            _ => {
+                self.registers[addr as usize] = byte;
+                self.sample();
+            }
+        }
+    }
+
+    fn transfer(&mut self, words: &mut [u8]) {
+        match words.first() {
+            Some(&cmd) if cmd & 0xe0 == READ_CMD => {
+                let addr = cmd & 0x1f;
+                assert!(
+                    words[1..].iter().all(|&byte| byte == FILLER),
+                    "Read of {:#04x} must be padded with {:#04x}, got {:02x?}",
+                    addr,
+                    FILLER,
+                    words,
+                );
+                // The command and two filler bytes are clocked out before any data
+                let len = words.len();
+                words[..len.min(3)].fill(FILLER);
+                for (i, word) in words.iter_mut().skip(3).enumerate() {
+                    *word = self.registers[(addr as usize + i) % self.registers.len()];
+                }
+            }
+            Some(&cmd) if cmd & 0xe0 == WRITE_CMD => {
+                assert!(
+                    words.len() & 1 == 0,
+                    "Writes are address and value pairs, got {:02x?}",
+                    words
+                );
+                for pair in words.chunks_mut(2) {
+                    self.write(pair[0] & 0x1f, pair[1]);
+                    pair.fill(FILLER);
+                }
+            }
+            Some(&cmd) => panic!("Unknown command {:#04x}", cmd),
+            None => (),
+        }
+    }
+}
+
+/// A simulated Pinnacle. Clones share the same device.
+#[derive(Clone)]
+pub struct Pinnacle(Rc<RefCell<Chip>>);
+
+impl Pinnacle {
+    pub fn new() -> Self {
+        Pinnacle(Rc::new(RefCell::new(Chip::new())))
+    }
+
+    /// The SPI bus connected to this device
+    pub fn spi(&self) -> Spi {
+        Spi(self.clone())
+    }
+
+    /// The data ready line from this device
+    pub fn data_ready(&self) -> DataReady {
+        DataReady(self.clone())
+    }
+
+    /// Queue samples to be played back after any already queued
+    pub fn play<I: IntoIterator<Item = Sample>>(&self, samples: I) {
+        let mut chip = self.0.borrow_mut();
+        chip.script.extend(samples);
+        chip.sample();
+    }
+
+    /// Make the chip stop or start responding. A chip that isn't responding never raises data
+    /// ready or command complete, like one that's unplugged or has its data ready line cut, so the
+    /// driver's waits time out.
+    pub fn set_responding(&self, responding: bool) {
+        self.0.borrow_mut().responding = responding;
+    }
+
+    /// Number of queued samples not yet loaded
+    pub fn pending(&self) -> usize {
+        self.0.borrow().script.len()
+    }
+
+    /// The current value of a register
+    pub fn register(&self, addr: u8) -> u8 {
+        self.0.borrow().registers[addr as usize]
+    }
+}
+
+impl Default for Pinnacle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// SPI side of a [`Pinnacle`]
+pub struct Spi(Pinnacle);
+
+impl Transfer<u8> for Spi {
+    type Error = Infallible;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        (self.0).0.borrow_mut().transfer(words);
+        Ok(words)
+    }
+}
+
+/// Data ready side of a [`Pinnacle`]
+pub struct DataReady(Pinnacle);
+
+impl InputPin for DataReady {
+    type Error = Infallible;
+
+    fn is_high(&self) -> Result<bool, Self::Error> {
+        Ok((self.0).0.borrow().data_ready())
+    }
+
+    fn is_low(&self) -> Result<bool, Self::Error> {
+        self.is_high().map(|high| !high)
+    }
+}
+
+/// A delay that returns immediately, since the simulator never makes the driver wait
+pub struct Delay;
+
+impl DelayMs<u8> for Delay {
+    fn delay_ms(&mut self, _ms: u8) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tm035035::config::anymeas::AnyMeasConfig;
+    use tm035035::config::calconfig1::*;
+    use tm035035::config::feedconfig1::DataMode;
+    use tm035035::config::samplerate::SampleRate;
+    use tm035035::config::sleepinterval::SleepInterval;
+    use tm035035::config::sleeptimer::SleepTimer;
+    use tm035035::config::sysconfig1::*;
+    use tm035035::{GlidePointError, Motion, Tm035035, Touch};
+
+    const TIMEOUT_MS: u32 = 10;
+
+    fn driver(pinnacle: &Pinnacle, data_mode: DataMode) -> Tm035035<Spi, DataReady> {
+        Tm035035::try_new(
+            pinnacle.spi(),
+            pinnacle.data_ready(),
+            data_mode,
+            &mut Delay,
+            TIMEOUT_MS,
+        )
+        .unwrap_or_else(|_| panic!("Simulated pad didn't respond"))
+    }
+
+    #[test]
+    fn relative_packets_decode() {
+        let pinnacle = Pinnacle::new();
+        let mut pad = driver(&pinnacle, DataMode::Relative);
+        // Intellimouse, taps and scrolling are on for relative mode, and GlideExtend is off
+        assert_eq!(pinnacle.register(0x05), 0b_0001_0001);
+
+        pinnacle.play([
+            Sample::Relative {
+                dx: -5,
+                dy: 200,
+                buttons: 0b_011,
+                wheel: -2,
+            },
+            Sample::Relative {
+                dx: 255,
+                dy: -256,
+                buttons: 0b_100,
+                wheel: 1,
+            },
+        ]);
+        match pad.next() {
+            Some(Ok(Touch::Moved(Motion {
+                dx: -5,
+                dy: 200,
+                primary: true,
+                secondary: true,
+                auxiliary: false,
+                wheel: -2,
+            }))) => (),
+            _ => panic!("Expected the first movement"),
+        }
+        match pad.next() {
+            Some(Ok(Touch::Moved(Motion {
+                dx: 255,
+                dy: -256,
+                primary: false,
+                secondary: false,
+                auxiliary: true,
+                wheel: 1,
+            }))) => (),
+            _ => panic!("Expected the second movement"),
+        }
+        assert!(pad.next().is_none());
+    }
+
+    #[test]
+    fn calibrate_sets_compensation_and_completes() {
+        let pinnacle = Pinnacle::new();
+        let mut pad = driver(&pinnacle, DataMode::Absolute);
+
+        let config = CalConfig1(
+            BackgroundComp::BackgroundCompEnable,
+            NerdComp::NerdCompDisable,
+            TrackErrorComp::TrackErrorCompEnable,
+            TapComp::TapCompDisable,
+        );
+        assert!(pad.calibrate(config, &mut Delay, TIMEOUT_MS).is_ok());
+        // The calibrate bit clears itself, and the driver clears command complete
+        assert_eq!(pinnacle.register(0x07), 0b_0000_1010);
+        assert_eq!(pinnacle.register(0x02), 0x00);
+    }
+
+    #[test]
+    fn power_sleep_and_sample_rate() {
+        let pinnacle = Pinnacle::new();
+        let mut pad = driver(&pinnacle, DataMode::Absolute);
+
+        assert!(pad.set_sample_rate(SampleRate::Rate40Sps).is_ok());
+        assert_eq!(pinnacle.register(0x09), 40);
+
+        let timing = pad.set_sleep_timing(
+            SleepInterval::from_millis(100),
+            SleepTimer::from_millis(2000),
+        );
+        assert!(timing.is_ok());
+        assert_eq!(pinnacle.register(0x0c), 50);
+        assert_eq!(pinnacle.register(0x0d), 4);
+
+        let power = SysConfig1(Shutdown::Active, SleepEnable::SleepEnable);
+        assert!(pad.set_power(power).is_ok());
+        assert_eq!(pinnacle.register(0x03), 0b_0000_0100);
+        let power = SysConfig1(Shutdown::Shutdown, SleepEnable::SleepDisable);
+        assert!(pad.set_power(power).is_ok());
+        assert_eq!(pinnacle.register(0x03), 0b_0000_0010);
+    }
+
+    #[test]
+    fn any_meas_measures_and_restores_settings() {
+        let pinnacle = Pinnacle::new();
+        let mut pad = driver(&pinnacle, DataMode::Absolute);
+        assert!(pad.set_sample_rate(SampleRate::Rate20Sps).is_ok());
+
+        assert!(pad.enter_any_meas(AnyMeasConfig::default()).is_ok());
+        // AnyMeas on, and the feed off
+        assert_eq!(pinnacle.register(0x03) & 0b_0000_1000, 0b_0000_1000);
+        assert_eq!(pinnacle.register(0x04) & 0b_0000_0001, 0);
+
+        pinnacle.play([Sample::Measurement(-1234), Sample::Measurement(42)]);
+        let first = pad.measure(0x0000_00ff, 0x0000_000f, &mut Delay, TIMEOUT_MS);
+        assert!(matches!(first, Ok(-1234)));
+        assert_eq!(pinnacle.register(0x16), 0xff);
+        assert_eq!(pinnacle.register(0x1a), 0x0f);
+        // Data ready means a measurement, not a touch
+        assert!(pad.next().is_none());
+        let second = pad.measure(0x0000_0001, 0, &mut Delay, TIMEOUT_MS);
+        assert!(matches!(second, Ok(42)));
+
+        assert!(pad.leave_any_meas(&mut Delay, TIMEOUT_MS).is_ok());
+        assert_eq!(pinnacle.register(0x03) & 0b_0000_1000, 0);
+        assert_eq!(pinnacle.register(0x04) & 0b_0000_0001, 0b_0000_0001);
+        assert_eq!(pinnacle.register(0x09), 20);
+
+        pinnacle.play([Sample::Absolute {
+            x: 1023,
+            y: 767,
+            z: 128,
+        }]);
+        match pad.next() {
+            Some(Ok(Touch::Touched(x, y, _))) => assert!(x.abs() < 0.01 && y.abs() < 0.01),
+            _ => panic!("Expected touches to resume"),
+        }
+    }
+
+    #[test]
+    fn unresponsive_chip_times_out() {
+        let pinnacle = Pinnacle::new();
+        pinnacle.set_responding(false);
+        let result = Tm035035::try_new(
+            pinnacle.spi(),
+            pinnacle.data_ready(),
+            DataMode::Absolute,
+            &mut Delay,
+            TIMEOUT_MS,
+        );
+        assert!(matches!(result, Err(GlidePointError::NotResponding)));
+
+        pinnacle.set_responding(true);
+        let mut pad = driver(&pinnacle, DataMode::Absolute);
+        pinnacle.set_responding(false);
+        let config = CalConfig1(
+            BackgroundComp::BackgroundCompEnable,
+            NerdComp::NerdCompEnable,
+            TrackErrorComp::TrackErrorCompEnable,
+            TapComp::TapCompEnable,
+        );
+        let result = pad.calibrate(config, &mut Delay, TIMEOUT_MS);
+        assert!(matches!(result, Err(GlidePointError::NotResponding)));
+
+        assert!(pad.enter_any_meas(AnyMeasConfig::default()).is_ok());
+        let result = pad.measure(1, 0, &mut Delay, TIMEOUT_MS);
+        assert!(matches!(result, Err(GlidePointError::NotResponding)));
+
+        // Samples don't arrive either
+        pinnacle.play([Sample::Absolute { x: 1, y: 1, z: 1 }]);
+        assert!(pad.next().is_none());
+    }
+}
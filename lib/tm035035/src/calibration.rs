@@ -0,0 +1,155 @@
+//! Per-unit range calibration
+//!
+//! Every pad reports a slightly different raw range. Tracing a finger around the edge of the pad
+//! records the range this particular pad actually reaches.
+
+use core::convert::TryInto;
+
+/// Smallest range that counts as a traced axis. Anything less means the trace missed the edge.
+const MIN_SPAN: u16 = 512;
+
+#[derive(Clone, Copy)]
+pub struct AxisRange {
+    pub min: u16,
+    pub max: u16,
+    pub center: u16,
+}
+
+#[derive(Clone, Copy)]
+pub struct Calibration {
+    pub x: AxisRange,
+    pub y: AxisRange,
+}
+
+impl Calibration {
+    /// Serialize for storage, as little-endian X then Y minimum, maximum and centre
+    pub fn to_bytes(&self) -> [u8; 12] {
+        let mut bytes = [0u8; 12];
+        let values = [
+            self.x.min,
+            self.x.max,
+            self.x.center,
+            self.y.min,
+            self.y.max,
+            self.y.center,
+        ];
+        for (chunk, value) in bytes.chunks_mut(2).zip(values.iter()) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Deserialize from storage. Returns `None` if the bytes don't hold a sensible range,
+    /// like erased flash.
+    pub fn from_bytes(bytes: &[u8; 12]) -> Option<Self> {
+        let value = |i: usize| u16::from_le_bytes(bytes[i * 2..i * 2 + 2].try_into().unwrap());
+        let axis = |i: usize| AxisRange {
+            min: value(i),
+            max: value(i + 1),
+            center: value(i + 2),
+        };
+        let calibration = Calibration {
+            x: axis(0),
+            y: axis(3),
+        };
+        if valid(&calibration.x) && valid(&calibration.y) {
+            Some(calibration)
+        } else {
+            None
+        }
+    }
+}
+
+fn valid(axis: &AxisRange) -> bool {
+    axis.max > axis.min
+        && axis.max - axis.min >= MIN_SPAN
+        && (axis.min..=axis.max).contains(&axis.center)
+        && axis.max <= 0x0fff
+}
+
+/// Records the raw range while the user traces the edge of the pad
+pub struct RangeCalibrator {
+    x: (u16, u16),
+    y: (u16, u16),
+}
+
+impl RangeCalibrator {
+    pub fn new() -> Self {
+        RangeCalibrator {
+            x: (u16::MAX, u16::MIN),
+            y: (u16::MAX, u16::MIN),
+        }
+    }
+
+    pub fn record(&mut self, x: u16, y: u16) {
+        self.x = (self.x.0.min(x), self.x.1.max(x));
+        self.y = (self.y.0.min(y), self.y.1.max(y));
+    }
+
+    /// The traced range, centred between the extremes on each axis.
+    /// Returns `None` if the trace didn't cover enough of the pad.
+    pub fn finish(&self) -> Option<Calibration> {
+        let axis = |(min, max): (u16, u16)| AxisRange {
+            min,
+            max,
+            center: min + max.saturating_sub(min) / 2,
+        };
+        let calibration = Calibration {
+            x: axis(self.x),
+            y: axis(self.y),
+        };
+        if valid(&calibration.x) && valid(&calibration.y) {
+            Some(calibration)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for RangeCalibrator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn traced(x: (u16, u16), y: (u16, u16)) -> Option<Calibration> {
+        let mut calibrator = RangeCalibrator::new();
+        calibrator.record(x.0, y.0);
+        calibrator.record(x.1, y.1);
+        calibrator.finish()
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let calibration = traced((100, 1900), (250, 1500)).unwrap();
+        let restored = Calibration::from_bytes(&calibration.to_bytes()).unwrap();
+        for (axis, restored) in [(calibration.x, restored.x), (calibration.y, restored.y)] {
+            assert_eq!(axis.min, restored.min);
+            assert_eq!(axis.max, restored.max);
+            assert_eq!(axis.center, restored.center);
+        }
+        assert_eq!((restored.x.center, restored.y.center), (1000, 875));
+    }
+
+    #[test]
+    fn erased_storage_rejected() {
+        assert!(Calibration::from_bytes(&[0xff; 12]).is_none());
+    }
+
+    #[test]
+    fn short_trace_rejected() {
+        assert!(traced((100, 100 + MIN_SPAN), (100, 100 + MIN_SPAN)).is_some());
+        assert!(traced((100, 100 + MIN_SPAN - 1), (100, 1900)).is_none());
+        assert!(traced((100, 1900), (100, 100 + MIN_SPAN - 1)).is_none());
+        assert!(RangeCalibrator::new().finish().is_none());
+
+        let mut bytes = traced((100, 1900), (100, 1900)).unwrap().to_bytes();
+        // X maximum, just short of the span
+        bytes[2..4].copy_from_slice(&(100 + MIN_SPAN - 1).to_le_bytes());
+        assert!(Calibration::from_bytes(&bytes).is_none());
+    }
+}
@@ -0,0 +1,17 @@
+pub mod anymeas;
+pub mod calconfig1;
+pub mod feedconfig1;
+pub mod feedconfig2;
+pub mod samplerate;
+pub mod sleepinterval;
+pub mod sleeptimer;
+pub mod sysconfig1;
+
+pub trait Register {
+    fn address(&self) -> u8;
+    fn value(&self) -> u8;
+}
+
+trait RegisterSetting {
+    fn value(&self) -> u8;
+}
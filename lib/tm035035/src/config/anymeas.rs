@@ -0,0 +1,151 @@
+//! AnyMeas mode configuration.
+//!
+//! In AnyMeas mode the position feed is off, and registers `0x05` through `0x0a` hold the ADC
+//! configuration instead of their usual feed and calibration settings.
+
+/// ADC configuration for AnyMeas measurements.
+pub struct AnyMeasConfig {
+    pub gain: AdcGain,
+    pub frequency: ExcitationFrequency,
+    pub sample_length: SampleLength,
+    pub mux: MuxControl,
+
+    /// Aperture width, in 125 ns steps
+    pub aperture_width: u8,
+
+    pub control: AnyMeasControl,
+}
+
+impl AnyMeasConfig {
+    /// Register values starting at `0x05`.
+    pub(crate) fn bytes(&self) -> [u8; 6] {
+        [
+            self.gain.value() | self.frequency.value(),
+            self.sample_length.value(),
+            self.mux.value(),
+            self.aperture_width,
+            0x00,
+            self.control.value(),
+        ]
+    }
+}
+
+impl Default for AnyMeasConfig {
+    fn default() -> Self {
+        Self {
+            gain: AdcGain::Gain1_0,
+            frequency: ExcitationFrequency::Freq0,
+            sample_length: SampleLength::Samples256,
+            mux: MuxControl {
+                ref0: false,
+                ref1: false,
+                pnp: true,
+                npn: true,
+            },
+            aperture_width: 0x04,
+            control: AnyMeasControl {
+                repeat: false,
+                power_idle: false,
+            },
+        }
+    }
+}
+
+/// ADC gain. Higher gain is more sensitive but saturates sooner.
+pub enum AdcGain {
+    /// 1x
+    Gain1_0,
+
+    /// 1.3x
+    Gain1_3,
+
+    /// 1.6x
+    Gain1_6,
+
+    /// 2x
+    Gain2_0,
+}
+
+impl AdcGain {
+    fn value(&self) -> u8 {
+        match self {
+            Self::Gain1_0 => 0b_1100_0000,
+            Self::Gain1_3 => 0b_1000_0000,
+            Self::Gain1_6 => 0b_0100_0000,
+            Self::Gain2_0 => 0b_0000_0000,
+        }
+    }
+}
+
+/// Electrode excitation frequency, from highest to lowest.
+pub enum ExcitationFrequency {
+    Freq0,
+    Freq1,
+    Freq2,
+    Freq3,
+    Freq4,
+    Freq5,
+    Freq6,
+    Freq7,
+}
+
+impl ExcitationFrequency {
+    fn value(&self) -> u8 {
+        match self {
+            Self::Freq0 => 0x02,
+            Self::Freq1 => 0x03,
+            Self::Freq2 => 0x04,
+            Self::Freq3 => 0x05,
+            Self::Freq4 => 0x06,
+            Self::Freq5 => 0x07,
+            Self::Freq6 => 0x09,
+            Self::Freq7 => 0x0b,
+        }
+    }
+}
+
+/// Number of ADC samples accumulated per measurement.
+pub enum SampleLength {
+    Samples128,
+    Samples256,
+    Samples512,
+}
+
+impl SampleLength {
+    fn value(&self) -> u8 {
+        match self {
+            Self::Samples128 => 1,
+            Self::Samples256 => 2,
+            Self::Samples512 => 4,
+        }
+    }
+}
+
+/// ADC input multiplexer selection.
+pub struct MuxControl {
+    pub ref0: bool,
+    pub ref1: bool,
+    pub pnp: bool,
+    pub npn: bool,
+}
+
+impl MuxControl {
+    fn value(&self) -> u8 {
+        (self.ref1 as u8) << 4 | (self.ref0 as u8) << 3 | (self.pnp as u8) << 2 | self.npn as u8
+    }
+}
+
+/// Measurement control flags.
+pub struct AnyMeasControl {
+    /// Keep measuring until told to stop, instead of once per request
+    pub repeat: bool,
+
+    /// Power down the ADC between measurements
+    pub power_idle: bool,
+}
+
+impl AnyMeasControl {
+    fn value(&self) -> u8 {
+        (self.repeat as u8) << 7 | (self.power_idle as u8) << 6
+    }
+}
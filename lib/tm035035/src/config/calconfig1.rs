@@ -0,0 +1,84 @@
+use super::{Register, RegisterSetting};
+
+/// Calibration configuration register 1 (r/w).
+///
+/// The calibration trigger bit is left clear; use
+/// [`Tm035035::calibrate`](crate::Tm035035::calibrate) to start a calibration.
+pub struct CalConfig1(
+    pub BackgroundComp,
+    pub NerdComp,
+    pub TrackErrorComp,
+    pub TapComp,
+);
+
+impl Register for CalConfig1 {
+    fn address(&self) -> u8 {
+        0x07
+    }
+
+    fn value(&self) -> u8 {
+        let Self(background, nerd, trackerror, tap) = self;
+
+        background.value() | nerd.value() | trackerror.value() | tap.value()
+    }
+}
+
+/// Background compensation, which slowly tracks baseline drift while the pad is untouched.
+pub enum BackgroundComp {
+    BackgroundCompDisable,
+    BackgroundCompEnable,
+}
+
+impl RegisterSetting for BackgroundComp {
+    fn value(&self) -> u8 {
+        match self {
+            Self::BackgroundCompDisable => 0b_0000_0000,
+            Self::BackgroundCompEnable => 0b_0000_0010,
+        }
+    }
+}
+
+/// No-finger-edge recovery (NERD) compensation, which recovers from a baseline taken while touched.
+pub enum NerdComp {
+    NerdCompDisable,
+    NerdCompEnable,
+}
+
+impl RegisterSetting for NerdComp {
+    fn value(&self) -> u8 {
+        match self {
+            Self::NerdCompDisable => 0b_0000_0000,
+            Self::NerdCompEnable => 0b_0000_0100,
+        }
+    }
+}
+
+/// Track error compensation, which recalibrates when the baseline is detected to be wrong.
+pub enum TrackErrorComp {
+    TrackErrorCompDisable,
+    TrackErrorCompEnable,
+}
+
+impl RegisterSetting for TrackErrorComp {
+    fn value(&self) -> u8 {
+        match self {
+            Self::TrackErrorCompDisable => 0b_0000_0000,
+            Self::TrackErrorCompEnable => 0b_0000_1000,
+        }
+    }
+}
+
+/// Tap compensation, which keeps quick taps from skewing the baseline.
+pub enum TapComp {
+    TapCompDisable,
+    TapCompEnable,
+}
+
+impl RegisterSetting for TapComp {
+    fn value(&self) -> u8 {
+        match self {
+            Self::TapCompDisable => 0b_0000_0000,
+            Self::TapCompEnable => 0b_0001_0000,
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use super::{Register, RegisterSetting};
+
+/// Feed configuration register 1 (r/w).
+pub struct FeedConfig1(
+    pub FeedEnable,
+    pub DataMode,
+    pub FilterDisable,
+    pub XDisable,
+    pub YDisable,
+    pub XInvert,
+    pub YInvert,
+);
+
+impl Register for FeedConfig1 {
+    fn address(&self) -> u8 {
+        0x04
+    }
+
+    fn value(&self) -> u8 {
+        let Self(feedenable, datamode, filterdisable, xdisable, ydisable, xinvert, yinvert) = self;
+
+        feedenable.value()
+            | datamode.value()
+            | filterdisable.value()
+            | xdisable.value()
+            | ydisable.value()
+            | xinvert.value()
+            | yinvert.value()
+    }
+}
+
+/// Enable the position data feed.
+pub enum FeedEnable {
+    /// No packets are reported
+    FeedDisable,
+
+    /// Packets are reported whenever there is new data
+    FeedEnable,
+}
+
+impl RegisterSetting for FeedEnable {
+    fn value(&self) -> u8 {
+        match self {
+            Self::FeedDisable => 0b_0000_0000,
+            Self::FeedEnable => 0b_0000_0001,
+        }
+    }
+}
+
+/// Packet format selection.
+#[derive(Clone, Copy, PartialEq)]
+pub enum DataMode {
+    /// Relative (mouse) packets with X/Y deltas, tap buttons and scroll
+    Relative,
+
+    /// Absolute packets with 12-bit X/Y position and Z level
+    Absolute,
+}
+
+impl RegisterSetting for DataMode {
+    fn value(&self) -> u8 {
+        match self {
+            Self::Relative => 0b_0000_0000,
+            Self::Absolute => 0b_0000_0010,
+        }
+    }
+}
+
+/// Disable the position filter.
+pub enum FilterDisable {
+    /// Position filter enabled
+    FilterEnable,
+
+    /// Position filter disabled
+    FilterDisable,
+}
+
+impl RegisterSetting for FilterDisable {
+    fn value(&self) -> u8 {
+        match self {
+            Self::FilterEnable => 0b_0000_0000,
+            Self::FilterDisable => 0b_0000_0100,
+        }
+    }
+}
+
+/// Disable X axis data.
+pub enum XDisable {
+    /// X data reported
+    XEnable,
+
+    /// X data not reported
+    XDisable,
+}
+
+impl RegisterSetting for XDisable {
+    fn value(&self) -> u8 {
+        match self {
+            Self::XEnable => 0b_0000_0000,
+            Self::XDisable => 0b_0000_1000,
+        }
+    }
+}
+
+/// Disable Y axis data.
+pub enum YDisable {
+    /// Y data reported
+    YEnable,
+
+    /// Y data not reported
+    YDisable,
+}
+
+impl RegisterSetting for YDisable {
+    fn value(&self) -> u8 {
+        match self {
+            Self::YEnable => 0b_0000_0000,
+            Self::YDisable => 0b_0001_0000,
+        }
+    }
+}
+
+/// Invert X axis data. Absolute mode only.
+pub enum XInvert {
+    /// X increases left to right
+    XNormal,
+
+    /// X increases right to left
+    XInverted,
+}
+
+impl RegisterSetting for XInvert {
+    fn value(&self) -> u8 {
+        match self {
+            Self::XNormal => 0b_0000_0000,
+            Self::XInverted => 0b_0100_0000,
+        }
+    }
+}
+
+/// Invert Y axis data. Absolute mode only.
+pub enum YInvert {
+    /// Y increases top to bottom
+    YNormal,
+
+    /// Y increases bottom to top
+    YInverted,
+}
+
+impl RegisterSetting for YInvert {
+    fn value(&self) -> u8 {
+        match self {
+            Self::YNormal => 0b_0000_0000,
+            Self::YInverted => 0b_1000_0000,
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use super::{Register, RegisterSetting};
+
+/// Feed configuration register 2 (r/w).
+pub struct FeedConfig2(
+    pub IntellimouseEnable,
+    pub AllTapDisable,
+    pub SecondaryTapDisable,
+    pub ScrollDisable,
+    pub GlideExtendDisable,
+    pub SwapXY,
+);
+
+impl Register for FeedConfig2 {
+    fn address(&self) -> u8 {
+        0x05
+    }
+
+    fn value(&self) -> u8 {
+        let Self(intellimouse, alltap, secondarytap, scroll, glideextend, swapxy) = self;
+
+        intellimouse.value()
+            | alltap.value()
+            | secondarytap.value()
+            | scroll.value()
+            | glideextend.value()
+            | swapxy.value()
+    }
+}
+
+/// Enable the Intellimouse scroll byte in relative packets.
+pub enum IntellimouseEnable {
+    /// 3-byte relative packets
+    IntellimouseDisable,
+
+    /// 4-byte relative packets, the last byte carrying scroll wheel counts
+    IntellimouseEnable,
+}
+
+impl RegisterSetting for IntellimouseEnable {
+    fn value(&self) -> u8 {
+        match self {
+            Self::IntellimouseDisable => 0b_0000_0000,
+            Self::IntellimouseEnable => 0b_0000_0001,
+        }
+    }
+}
+
+/// Disable all tap detection. Relative mode only.
+pub enum AllTapDisable {
+    /// Taps are reported as button presses
+    TapEnable,
+
+    /// Taps are not reported
+    TapDisable,
+}
+
+impl RegisterSetting for AllTapDisable {
+    fn value(&self) -> u8 {
+        match self {
+            Self::TapEnable => 0b_0000_0000,
+            Self::TapDisable => 0b_0000_0010,
+        }
+    }
+}
+
+/// Disable secondary tap detection (a tap in the upper right corner). Relative mode only.
+pub enum SecondaryTapDisable {
+    /// Secondary taps are reported as the secondary button
+    SecondaryTapEnable,
+
+    /// Secondary taps are reported as the primary button
+    SecondaryTapDisable,
+}
+
+impl RegisterSetting for SecondaryTapDisable {
+    fn value(&self) -> u8 {
+        match self {
+            Self::SecondaryTapEnable => 0b_0000_0000,
+            Self::SecondaryTapDisable => 0b_0000_0100,
+        }
+    }
+}
+
+/// Disable scroll detection along the right edge. Relative mode only.
+pub enum ScrollDisable {
+    /// Edge scrolling enabled
+    ScrollEnable,
+
+    /// Edge scrolling disabled
+    ScrollDisable,
+}
+
+impl RegisterSetting for ScrollDisable {
+    fn value(&self) -> u8 {
+        match self {
+            Self::ScrollEnable => 0b_0000_0000,
+            Self::ScrollDisable => 0b_0000_1000,
+        }
+    }
+}
+
+/// Disable GlideExtend, which keeps motion going when a finger reaches the edge. Relative mode only.
+pub enum GlideExtendDisable {
+    /// GlideExtend enabled
+    GlideExtendEnable,
+
+    /// GlideExtend disabled
+    GlideExtendDisable,
+}
+
+impl RegisterSetting for GlideExtendDisable {
+    fn value(&self) -> u8 {
+        match self {
+            Self::GlideExtendEnable => 0b_0000_0000,
+            Self::GlideExtendDisable => 0b_0001_0000,
+        }
+    }
+}
+
+/// Swap X and Y axes. Relative mode only.
+pub enum SwapXY {
+    /// X and Y as wired
+    XYNormal,
+
+    /// X and Y swapped
+    XYSwapped,
+}
+
+impl RegisterSetting for SwapXY {
+    fn value(&self) -> u8 {
+        match self {
+            Self::XYNormal => 0b_0000_0000,
+            Self::XYSwapped => 0b_1000_0000,
+        }
+    }
+}
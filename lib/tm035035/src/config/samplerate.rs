@@ -0,0 +1,43 @@
+use super::Register;
+
+/// Sample rate register (r/w). Lower rates trade latency for power.
+pub enum SampleRate {
+    /// 10 samples per second
+    Rate10Sps,
+
+    /// 20 samples per second
+    Rate20Sps,
+
+    /// 40 samples per second
+    Rate40Sps,
+
+    /// 60 samples per second
+    Rate60Sps,
+
+    /// 80 samples per second
+    Rate80Sps,
+
+    /// 100 samples per second
+    Rate100Sps,
+
+    /// 200 samples per second
+    Rate200Sps,
+}
+
+impl Register for SampleRate {
+    fn address(&self) -> u8 {
+        0x09
+    }
+
+    fn value(&self) -> u8 {
+        match self {
+            Self::Rate10Sps => 10,
+            Self::Rate20Sps => 20,
+            Self::Rate40Sps => 40,
+            Self::Rate60Sps => 60,
+            Self::Rate80Sps => 80,
+            Self::Rate100Sps => 100,
+            Self::Rate200Sps => 200,
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use super::Register;
+
+/// Sleep interval register (r/w). Time between samples while asleep, in 2 ms steps.
+pub struct SleepInterval(pub u8);
+
+impl SleepInterval {
+    pub fn from_millis(millis: u16) -> Self {
+        Self((millis / 2).min(u8::MAX.into()) as u8)
+    }
+}
+
+impl Register for SleepInterval {
+    fn address(&self) -> u8 {
+        0x0c
+    }
+
+    fn value(&self) -> u8 {
+        self.0
+    }
+}
@@ -0,0 +1,20 @@
+use super::Register;
+
+/// Sleep timer register (r/w). Time without a touch before going to sleep, in 500 ms steps.
+pub struct SleepTimer(pub u8);
+
+impl SleepTimer {
+    pub fn from_millis(millis: u32) -> Self {
+        Self((millis / 500).min(u8::MAX.into()) as u8)
+    }
+}
+
+impl Register for SleepTimer {
+    fn address(&self) -> u8 {
+        0x0d
+    }
+
+    fn value(&self) -> u8 {
+        self.0
+    }
+}
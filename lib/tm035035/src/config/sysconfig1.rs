@@ -0,0 +1,58 @@
+use super::{Register, RegisterSetting};
+
+/// System configuration register 1 (r/w).
+///
+/// The reset bit is left clear; the device is reset when the driver is created.
+pub struct SysConfig1(pub Shutdown, pub SleepEnable);
+
+impl Register for SysConfig1 {
+    fn address(&self) -> u8 {
+        0x03
+    }
+
+    fn value(&self) -> u8 {
+        let Self(shutdown, sleepenable) = self;
+
+        shutdown.value() | sleepenable.value()
+    }
+}
+
+/// Shutdown mode. Nothing is sensed until shutdown is disabled again.
+pub enum Shutdown {
+    /// Normal operation
+    Active,
+
+    /// Lowest power state, which doesn't wake on touch
+    Shutdown,
+}
+
+impl RegisterSetting for Shutdown {
+    fn value(&self) -> u8 {
+        match self {
+            Self::Active => 0b_0000_0000,
+            Self::Shutdown => 0b_0000_0010,
+        }
+    }
+}
+
+/// Automatic sleep mode.
+///
+/// When enabled, the device drops to the [`SleepInterval`](super::sleepinterval::SleepInterval)
+/// sample rate after [`SleepTimer`](super::sleeptimer::SleepTimer) passes without a touch,
+/// and returns to full rate as soon as it's touched.
+pub enum SleepEnable {
+    /// Always sample at the full rate
+    SleepDisable,
+
+    /// Sleep when idle
+    SleepEnable,
+}
+
+impl RegisterSetting for SleepEnable {
+    fn value(&self) -> u8 {
+        match self {
+            Self::SleepDisable => 0b_0000_0000,
+            Self::SleepEnable => 0b_0000_0100,
+        }
+    }
+}
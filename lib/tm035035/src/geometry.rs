@@ -0,0 +1,165 @@
+//! Geometry of the round TM035035 sensor
+//!
+//! Absolute positions are mapped onto the unit disk, with the same scale on both axes, so
+//! distances and angles mean the same thing in every direction.
+
+use libm::{atan2f, sqrtf};
+
+use crate::calibration::Calibration;
+
+/// The raw range the sensor actually reports in absolute mode. The ADC range is 0..2047 on X
+/// and 0..1535 on Y, but the edges of the sensor are never reached.
+const MIN_X: f32 = 127.0;
+const MAX_X: f32 = 1919.0;
+const MIN_Y: f32 = 63.0;
+const MAX_Y: f32 = 1471.0;
+
+pub struct Geometry {
+    /// Raw X at the centre of the sensor
+    pub center_x: f32,
+
+    /// Raw Y at the centre of the sensor
+    pub center_y: f32,
+
+    /// Raw X counts from the centre to the edge
+    pub radius_x: f32,
+
+    /// Raw Y counts from the centre to the edge
+    pub radius_y: f32,
+
+    /// Width of the outer ring, as a fraction of the radius
+    pub rim_width: f32,
+}
+
+impl Default for Geometry {
+    /// The range a typical pad reaches, for pads that haven't been calibrated
+    fn default() -> Self {
+        Geometry {
+            center_x: (MIN_X + MAX_X) / 2.0,
+            center_y: (MIN_Y + MAX_Y) / 2.0,
+            radius_x: (MAX_X - MIN_X) / 2.0,
+            radius_y: (MAX_Y - MIN_Y) / 2.0,
+            rim_width: 0.1,
+        }
+    }
+}
+
+impl Geometry {
+    /// The geometry of one particular pad, from a traced calibration
+    pub fn calibrated(calibration: &Calibration, rim_width: f32) -> Self {
+        let Calibration { x, y } = calibration;
+        Geometry {
+            center_x: x.center.into(),
+            center_y: y.center.into(),
+            radius_x: (x.max - x.min) as f32 / 2.0,
+            radius_y: (y.max - y.min) as f32 / 2.0,
+            rim_width,
+        }
+    }
+
+    /// Map a raw position onto the unit disk. Positions outside the disk are pulled in to its edge.
+    pub fn normalize(&self, x: u16, y: u16) -> (f32, f32) {
+        let x = (x as f32 - self.center_x) / self.radius_x;
+        let y = (y as f32 - self.center_y) / self.radius_y;
+        clamp(x, y)
+    }
+
+    /// Whether a normalized position is in the outer ring
+    pub fn is_rim(&self, x: f32, y: f32) -> bool {
+        polar(x, y).0 >= 1.0 - self.rim_width
+    }
+}
+
+/// Pull a position outside the unit disk in to its edge
+pub fn clamp(x: f32, y: f32) -> (f32, f32) {
+    let (radius, _) = polar(x, y);
+    if radius > 1.0 {
+        (x / radius, y / radius)
+    } else {
+        (x, y)
+    }
+}
+
+/// Radius and angle of a normalized position. The angle is in radians from the +X axis
+/// toward the +Y axis, from -π to π.
+pub fn polar(x: f32, y: f32) -> (f32, f32) {
+    (sqrtf(x * x + y * y), atan2f(y, x))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::f32::consts::{FRAC_PI_2, PI};
+
+    fn close((x, y): (f32, f32), (expected_x, expected_y): (f32, f32)) -> bool {
+        (x - expected_x).abs() < 1e-3 && (y - expected_y).abs() < 1e-3
+    }
+
+    #[test]
+    fn normalize_reaches_the_edge() {
+        let geometry = Geometry::default();
+        assert!(close(geometry.normalize(1023, 767), (0.0, 0.0)));
+        assert!(close(geometry.normalize(127, 767), (-1.0, 0.0)));
+        assert!(close(geometry.normalize(1919, 767), (1.0, 0.0)));
+        assert!(close(geometry.normalize(1023, 63), (0.0, -1.0)));
+        assert!(close(geometry.normalize(1023, 1471), (0.0, 1.0)));
+    }
+
+    #[test]
+    fn normalize_clamps_outside_the_disk() {
+        let geometry = Geometry::default();
+        assert!(close(geometry.normalize(0, 767), (-1.0, 0.0)));
+        assert!(close(geometry.normalize(1023, 1535), (0.0, 1.0)));
+        let (x, y) = geometry.normalize(1919, 1471);
+        assert!(close((x, y), (0.5f32.sqrt(), 0.5f32.sqrt())));
+    }
+
+    #[test]
+    fn calibrated_range() {
+        use crate::calibration::AxisRange;
+
+        let calibration = Calibration {
+            x: AxisRange {
+                min: 200,
+                max: 1800,
+                center: 1000,
+            },
+            y: AxisRange {
+                min: 100,
+                max: 1300,
+                center: 700,
+            },
+        };
+        let geometry = Geometry::calibrated(&calibration, 0.1);
+        assert!(close(geometry.normalize(1000, 700), (0.0, 0.0)));
+        assert!(close(geometry.normalize(1800, 700), (1.0, 0.0)));
+        assert!(close(geometry.normalize(1000, 100), (0.0, -1.0)));
+    }
+
+    #[test]
+    fn clamp_only_outside() {
+        assert!(close(clamp(0.3, -0.4), (0.3, -0.4)));
+        assert!(close(clamp(3.0, 4.0), (0.6, 0.8)));
+        assert!(close(clamp(0.0, -2.0), (0.0, -1.0)));
+    }
+
+    #[test]
+    fn polar_angles() {
+        assert!(close(polar(0.0, 0.0), (0.0, 0.0)));
+        assert!(close(polar(1.0, 0.0), (1.0, 0.0)));
+        assert!(close(polar(0.0, 0.5), (0.5, FRAC_PI_2)));
+        assert!(close(polar(0.0, -0.5), (0.5, -FRAC_PI_2)));
+        assert!(close(polar(-1.0, 0.0), (1.0, PI)));
+    }
+
+    #[test]
+    fn rim_boundary() {
+        let geometry = Geometry::default();
+        assert!(!geometry.is_rim(0.0, 0.0));
+        assert!(!geometry.is_rim(0.0, -0.89));
+        assert!(geometry.is_rim(0.0, -0.9));
+        assert!(geometry.is_rim(0.9, 0.0));
+        assert!(geometry.is_rim(1.0, 0.0));
+    }
+}
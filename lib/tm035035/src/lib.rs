@@ -1,12 +1,38 @@
 #![no_std]
 #![feature(never_type)]
 
+use core::fmt;
+
+use embedded_hal::blocking::delay::DelayMs;
 use embedded_hal::blocking::spi::Transfer;
 use embedded_hal::digital::v2::InputPin;
 
+use self::calibration::{Calibration, RangeCalibrator};
+use self::config::anymeas::*;
+use self::config::calconfig1::*;
+use self::config::feedconfig1::*;
+use self::config::feedconfig2::*;
+use self::config::samplerate::*;
+use self::config::sleepinterval::*;
+use self::config::sleeptimer::*;
+use self::config::sysconfig1::*;
+use self::config::Register;
+use self::geometry::Geometry;
+
+pub mod calibration;
+pub mod config;
+pub mod geometry;
+
 pub struct Tm035035<S, DR> {
     spi: S,
     data_ready: DR,
+    data_mode: DataMode,
+    any_meas: bool,
+    geometry: Geometry,
+    range_calibrator: Option<RangeCalibrator>,
+
+    /// Register values set through the driver, by address, so they can be restored after a reset
+    settings: [Option<u8>; 0x20],
 }
 
 pub enum GlidePointError<S, DR>
@@ -16,21 +42,57 @@ where
 {
     TransferError(S::Error),
     DataReadyError(DR::Error),
+
+    /// The device didn't signal completion before the timeout ran out.
+    /// It's likely unplugged, or its data ready line isn't connected.
+    NotResponding,
+}
+
+impl<S, DR> fmt::Debug for GlidePointError<S, DR>
+where
+    S: Transfer<u8>,
+    S::Error: fmt::Debug,
+    DR: InputPin,
+    DR::Error: fmt::Debug,
+{
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GlidePointError::TransferError(error) => {
+                write!(formatter, "TransferError({:?})", error)
+            }
+            GlidePointError::DataReadyError(error) => {
+                write!(formatter, "DataReadyError({:?})", error)
+            }
+            GlidePointError::NotResponding => write!(formatter, "NotResponding"),
+        }
+    }
 }
 
 impl Tm035035<!, !> {
-    pub fn try_new<S, DR>(spi: S, data_ready: DR) -> Result<Tm035035<S, DR>, GlidePointError<S, DR>>
+    /// Reset and configure the device, giving up after `timeout_ms` without a response.
+    pub fn try_new<S, DR, D>(
+        spi: S,
+        data_ready: DR,
+        data_mode: DataMode,
+        delay: &mut D,
+        timeout_ms: u32,
+    ) -> Result<Tm035035<S, DR>, GlidePointError<S, DR>>
     where
         S: Transfer<u8>,
         DR: InputPin,
+        D: DelayMs<u8>,
     {
-        let mut glide_point = Tm035035 { spi, data_ready };
-
-        glide_point.reset()?;
+        let mut glide_point = Tm035035 {
+            spi,
+            data_ready,
+            data_mode,
+            any_meas: false,
+            geometry: Geometry::default(),
+            range_calibrator: None,
+            settings: [None; 0x20],
+        };
 
-        glide_point.write_byte(0x03, 0b_0000_0000)?;
-        glide_point.write_byte(0x05, 0b_0001_1110)?;
-        glide_point.write_byte(0x04, 0b_0000_0011)?;
+        glide_point.initialize(delay, timeout_ms)?;
 
         Ok(glide_point)
     }
@@ -44,9 +106,6 @@ where
     const READ_CMD: u8 = 0xa0;
     const WRITE_CMD: u8 = 0x80;
 
-    const MAX_X: f32 = 2047.0;
-    const MAX_Y: f32 = 1535.0;
-
     fn transfer<'a>(&mut self, buf: &'a mut [u8]) -> Result<&'a [u8], GlidePointError<S, DR>> {
         self.spi
             .transfer(buf)
@@ -72,6 +131,22 @@ where
         Ok(())
     }
 
+    fn configure<R: Register>(&mut self, register: R) -> Result<(), GlidePointError<S, DR>> {
+        self.write_byte(register.address(), register.value())
+    }
+
+    /// Configure a register, and remember the value so `initialize` can restore it
+    fn configure_stored<R: Register>(&mut self, register: R) -> Result<(), GlidePointError<S, DR>> {
+        self.settings[register.address() as usize] = Some(register.value());
+        self.configure(register)
+    }
+
+    fn status(&mut self) -> Result<u8, GlidePointError<S, DR>> {
+        let mut buf = [0u8; 1];
+        self.read_bytes(0x02, &mut buf)?;
+        Ok(buf[0])
+    }
+
     fn clear_flags(&mut self) -> Result<(), GlidePointError<S, DR>> {
         let result = self.write_byte(0x02, 0x00)?;
         Ok(result)
@@ -83,14 +158,230 @@ where
             .map_err(GlidePointError::DataReadyError)
     }
 
-    fn reset(&mut self) -> Result<(), GlidePointError<S, DR>> {
+    /// Reset the device and apply the configuration for the current data mode, along with any
+    /// settings made since the driver was created.
+    fn initialize<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        timeout_ms: u32,
+    ) -> Result<(), GlidePointError<S, DR>> {
+        self.reset(delay, timeout_ms)?;
+
+        self.configure(SysConfig1(Shutdown::Active, SleepEnable::SleepDisable))?;
+        for addr in 0..self.settings.len() as u8 {
+            if let Some(byte) = self.settings[addr as usize] {
+                self.write_byte(addr, byte)?;
+            }
+        }
+        self.configure(match self.data_mode {
+            // Taps, scrolling and GlideExtend only make sense for relative packets
+            DataMode::Absolute => FeedConfig2(
+                IntellimouseEnable::IntellimouseDisable,
+                AllTapDisable::TapDisable,
+                SecondaryTapDisable::SecondaryTapDisable,
+                ScrollDisable::ScrollDisable,
+                GlideExtendDisable::GlideExtendDisable,
+                SwapXY::XYNormal,
+            ),
+            DataMode::Relative => FeedConfig2(
+                IntellimouseEnable::IntellimouseEnable,
+                AllTapDisable::TapEnable,
+                SecondaryTapDisable::SecondaryTapEnable,
+                ScrollDisable::ScrollEnable,
+                GlideExtendDisable::GlideExtendDisable,
+                SwapXY::XYNormal,
+            ),
+        })?;
+        self.configure(FeedConfig1(
+            FeedEnable::FeedEnable,
+            self.data_mode,
+            FilterDisable::FilterEnable,
+            XDisable::XEnable,
+            YDisable::YEnable,
+            XInvert::XNormal,
+            YInvert::YNormal,
+        ))?;
+
+        Ok(())
+    }
+
+    /// Poll `done` every millisecond until it returns true, or fail after `timeout_ms`.
+    fn wait_until<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        timeout_ms: u32,
+        mut done: impl FnMut(&mut Self) -> Result<bool, GlidePointError<S, DR>>,
+    ) -> Result<(), GlidePointError<S, DR>> {
+        for _ in 0..=timeout_ms {
+            if done(self)? {
+                return Ok(());
+            }
+            delay.delay_ms(1);
+        }
+        Err(GlidePointError::NotResponding)
+    }
+
+    fn reset<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        timeout_ms: u32,
+    ) -> Result<(), GlidePointError<S, DR>> {
         self.write_byte(0x03, 0b_0000_0001)?;
-        while !self.data_ready()? {}
+        self.wait_until(delay, timeout_ms, |glide_point| glide_point.data_ready())?;
         self.clear_flags()?;
         Ok(())
     }
 
+    /// Recalibrate the sensor baseline, and set which compensation methods run afterward.
+    ///
+    /// The pad should be untouched while calibrating, or the baseline will include the touch.
+    pub fn calibrate<D: DelayMs<u8>>(
+        &mut self,
+        config: CalConfig1,
+        delay: &mut D,
+        timeout_ms: u32,
+    ) -> Result<(), GlidePointError<S, DR>> {
+        self.clear_flags()?;
+        self.settings[config.address() as usize] = Some(config.value());
+        self.write_byte(config.address(), config.value() | 0b_0000_0001)?;
+        // Wait for the command complete flag
+        self.wait_until(delay, timeout_ms, |glide_point| {
+            Ok(glide_point.status()? & 0b_0000_1000 != 0)
+        })?;
+        self.clear_flags()?;
+        Ok(())
+    }
+
+    /// Enter or leave shutdown, and enable or disable automatic sleep.
+    ///
+    /// A shut down device doesn't wake on touch; call this again with [`Shutdown::Active`]
+    /// to resume. A sleeping device wakes by itself as soon as it's touched.
+    pub fn set_power(&mut self, config: SysConfig1) -> Result<(), GlidePointError<S, DR>> {
+        self.configure_stored(config)
+    }
+
+    /// Set how often the device samples while awake.
+    pub fn set_sample_rate(&mut self, rate: SampleRate) -> Result<(), GlidePointError<S, DR>> {
+        self.configure_stored(rate)
+    }
+
+    /// Set how often the device samples while asleep, and how long it waits before sleeping.
+    pub fn set_sleep_timing(
+        &mut self,
+        interval: SleepInterval,
+        timer: SleepTimer,
+    ) -> Result<(), GlidePointError<S, DR>> {
+        self.configure_stored(interval)?;
+        self.configure_stored(timer)
+    }
+
+    /// Switch to AnyMeas mode for raw capacitance measurements. The position feed stops
+    /// until [`leave_any_meas`](Self::leave_any_meas) is called.
+    pub fn enter_any_meas(&mut self, config: AnyMeasConfig) -> Result<(), GlidePointError<S, DR>> {
+        self.configure(FeedConfig1(
+            FeedEnable::FeedDisable,
+            self.data_mode,
+            FilterDisable::FilterEnable,
+            XDisable::XEnable,
+            YDisable::YEnable,
+            XInvert::XNormal,
+            YInvert::YNormal,
+        ))?;
+        self.write_byte(0x03, 0b_0000_1000)?;
+        self.any_meas = true;
+        self.configure_any_meas(config)
+    }
+
+    /// Change the ADC configuration while in AnyMeas mode.
+    pub fn configure_any_meas(
+        &mut self,
+        config: AnyMeasConfig,
+    ) -> Result<(), GlidePointError<S, DR>> {
+        for (addr, byte) in (0x05..).zip(config.bytes().iter()) {
+            self.write_byte(addr, *byte)?;
+        }
+        self.clear_flags()
+    }
+
+    /// Take a single AnyMeas measurement.
+    ///
+    /// `toggle` selects which electrodes are driven, and `polarity` which of those are driven
+    /// inverted. Each is a bit mask with one bit per electrode.
+    pub fn measure<D: DelayMs<u8>>(
+        &mut self,
+        toggle: u32,
+        polarity: u32,
+        delay: &mut D,
+        timeout_ms: u32,
+    ) -> Result<i16, GlidePointError<S, DR>> {
+        let mut masks = [0u8; 8];
+        masks[..4].copy_from_slice(&toggle.to_be_bytes());
+        masks[4..].copy_from_slice(&polarity.to_be_bytes());
+        for (addr, byte) in (0x13..).zip(masks.iter()) {
+            self.write_byte(addr, *byte)?;
+        }
+        self.clear_flags()?;
+
+        // Start the measurement, staying in AnyMeas mode
+        self.write_byte(0x03, 0b_0001_1000)?;
+        self.wait_until(delay, timeout_ms, |glide_point| glide_point.data_ready())?;
+
+        let mut buf = [0u8; 2];
+        self.read_bytes(0x11, &mut buf)?;
+        self.clear_flags()?;
+        Ok(i16::from_be_bytes(buf))
+    }
+
+    /// Leave AnyMeas mode. The device is reset and reconfigured for its original data mode, and
+    /// the power, sample rate, sleep and compensation settings are restored. The reset takes a
+    /// fresh baseline, as if [`calibrate`](Self::calibrate) had been called.
+    pub fn leave_any_meas<D: DelayMs<u8>>(
+        &mut self,
+        delay: &mut D,
+        timeout_ms: u32,
+    ) -> Result<(), GlidePointError<S, DR>> {
+        self.any_meas = false;
+        self.initialize(delay, timeout_ms)
+    }
+
+    /// How absolute positions are mapped onto the unit disk
+    pub fn geometry(&self) -> &Geometry {
+        &self.geometry
+    }
+
+    pub fn set_geometry(&mut self, geometry: Geometry) {
+        self.geometry = geometry;
+    }
+
+    /// Start recording the raw range. Have the user trace the edge of the pad,
+    /// then call [`finish_range_calibration`](Self::finish_range_calibration).
+    pub fn start_range_calibration(&mut self) {
+        self.range_calibrator = Some(RangeCalibrator::new());
+    }
+
+    /// Stop recording the raw range, and normalize positions to it from now on.
+    ///
+    /// Returns the calibration so it can be stored and restored later with
+    /// [`set_geometry`](Self::set_geometry), or `None` if the trace didn't cover the pad.
+    /// In that case the geometry is left alone.
+    pub fn finish_range_calibration(&mut self) -> Option<Calibration> {
+        let calibration = self.range_calibrator.take()?.finish()?;
+        self.geometry = Geometry::calibrated(&calibration, self.geometry.rim_width);
+        Some(calibration)
+    }
+
     fn read_touch(&mut self) -> Result<Option<Touch>, GlidePointError<S, DR>> {
+        if self.any_meas {
+            // Data ready signals a measurement, not a packet
+            return Ok(None);
+        }
+        match self.data_mode {
+            DataMode::Absolute => self.read_absolute(),
+            DataMode::Relative => self.read_relative(),
+        }
+    }
+
+    fn read_absolute(&mut self) -> Result<Option<Touch>, GlidePointError<S, DR>> {
         Ok(if self.data_ready()? {
             let mut buf = [0u8; 4];
             self.read_bytes(0x14, &mut buf)?;
@@ -104,8 +395,11 @@ where
                 let x = x_low as u16 | (((xy_high & 0x0f) as u16) << 8);
                 let y = y_low as u16 | (((xy_high & 0xf0) as u16) << 4);
 
-                let x = (x as f32 / Self::MAX_X) * 2.0 - 1.0;
-                let y = (y as f32 / Self::MAX_Y) * 2.0 - 1.0;
+                if let Some(range_calibrator) = &mut self.range_calibrator {
+                    range_calibrator.record(x, y);
+                }
+
+                let (x, y) = self.geometry.normalize(x, y);
                 let z = pressure as f32 / 255.0;
 
                 Some(Touch::Touched(x, y, z))
@@ -114,11 +408,63 @@ where
             None
         })
     }
+
+    fn read_relative(&mut self) -> Result<Option<Touch>, GlidePointError<S, DR>> {
+        Ok(if self.data_ready()? {
+            let mut buf = [0u8; 4];
+            self.read_bytes(0x12, &mut buf)?;
+            self.clear_flags()?;
+
+            let [flags, x_low, y_low, wheel] = buf;
+
+            // X and Y deltas are 9-bit two's complement, with the sign bits in the first byte
+            let dx = if flags & 0x10 != 0 {
+                x_low as i16 - 0x100
+            } else {
+                x_low as i16
+            };
+            let dy = if flags & 0x20 != 0 {
+                y_low as i16 - 0x100
+            } else {
+                y_low as i16
+            };
+
+            Some(Touch::Moved(Motion {
+                dx,
+                dy,
+                primary: flags & 0x01 != 0,
+                secondary: flags & 0x02 != 0,
+                auxiliary: flags & 0x04 != 0,
+                wheel: wheel as i8,
+            }))
+        } else {
+            None
+        })
+    }
 }
 
 pub enum Touch {
+    /// X and Y on the unit disk, and Z from 0 to 1
     Touched(f32, f32, f32),
     NotTouched,
+    Moved(Motion),
+}
+
+/// A relative-mode packet. Deltas are in sensor counts, as accelerated by the Pinnacle.
+pub struct Motion {
+    pub dx: i16,
+    pub dy: i16,
+
+    /// Tap anywhere on the pad
+    pub primary: bool,
+
+    /// Tap in the upper right corner
+    pub secondary: bool,
+
+    pub auxiliary: bool,
+
+    /// Intellimouse scroll counts
+    pub wheel: i8,
 }
 
 impl<S: Transfer<u8>, DR: InputPin> Iterator for Tm035035<S, DR> {
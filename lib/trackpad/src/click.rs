@@ -0,0 +1,132 @@
+//! Pressure clicks: turns touch Z into a click button, for pads without a dome switch
+
+use core::time::Duration;
+
+use crate::session::Event;
+
+pub struct ClickConfig {
+    /// Z at or above which a touch starts pressing
+    pub press_z: f32,
+
+    /// Z below which a press releases. Lower than `press_z`, so a press doesn't chatter.
+    pub release_z: f32,
+
+    /// How long Z has to stay above `press_z` before the press counts
+    pub min_hold: Duration,
+}
+
+impl Default for ClickConfig {
+    fn default() -> Self {
+        ClickConfig {
+            press_z: 0.6,
+            release_z: 0.45,
+            min_hold: Duration::from_millis(20),
+        }
+    }
+}
+
+pub struct Click {
+    config: ClickConfig,
+    pressing_since: Option<Duration>,
+    pressed: bool,
+}
+
+impl Click {
+    pub fn new(config: ClickConfig) -> Self {
+        Click {
+            config,
+            pressing_since: None,
+            pressed: false,
+        }
+    }
+
+    /// Returns whether the pad is clicked after this event
+    pub fn update(&mut self, event: &Event) -> bool {
+        match *event {
+            Event::TouchDown { at, z, .. } | Event::Move { at, z, .. } => {
+                if self.pressed {
+                    if z < self.config.release_z {
+                        self.release();
+                    }
+                } else if z >= self.config.press_z {
+                    let since = *self.pressing_since.get_or_insert(at);
+                    self.pressed = at - since >= self.config.min_hold;
+                } else {
+                    self.pressing_since = None;
+                }
+            }
+            Event::Lift { .. } | Event::Hover { .. } => self.release(),
+        }
+        self.pressed
+    }
+
+    fn release(&mut self) {
+        self.pressed = false;
+        self.pressing_since = None;
+    }
+
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn pressing(at: u64, z: f32) -> Event {
+        Event::Move {
+            at: ms(at),
+            x: 0.0,
+            y: 0.0,
+            z,
+            duration: ms(at),
+        }
+    }
+
+    #[test]
+    fn press_needs_hold() {
+        let mut click = Click::new(ClickConfig::default());
+        assert!(!click.update(&pressing(0, 0.7)));
+        assert!(!click.update(&pressing(10, 0.7)));
+        assert!(click.update(&pressing(20, 0.7)));
+        assert!(click.is_pressed());
+    }
+
+    #[test]
+    fn brief_press_starts_over() {
+        let mut click = Click::new(ClickConfig::default());
+        click.update(&pressing(0, 0.7));
+        click.update(&pressing(10, 0.3));
+        assert!(!click.update(&pressing(20, 0.7)));
+        assert!(click.update(&pressing(40, 0.7)));
+    }
+
+    #[test]
+    fn release_has_hysteresis() {
+        let mut click = Click::new(ClickConfig::default());
+        click.update(&pressing(0, 0.7));
+        click.update(&pressing(20, 0.7));
+
+        // Between the thresholds holds the press
+        assert!(click.update(&pressing(30, 0.5)));
+        assert!(!click.update(&pressing(40, 0.4)));
+    }
+
+    #[test]
+    fn lift_releases() {
+        let mut click = Click::new(ClickConfig::default());
+        click.update(&pressing(0, 0.7));
+        click.update(&pressing(20, 0.7));
+        assert!(!click.update(&Event::Lift {
+            at: ms(20),
+            x: 0.0,
+            y: 0.0,
+            duration: ms(20),
+        }));
+    }
+}
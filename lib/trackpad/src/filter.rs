@@ -0,0 +1,241 @@
+//! Jitter filters for noisy analog signals, one axis at a time
+
+use core::f32::consts::PI;
+use core::time::Duration;
+
+use tm035035::Touch;
+
+/// How to smooth one axis
+#[derive(Clone, Copy, Default)]
+pub enum FilterConfig {
+    /// Pass values straight through
+    #[default]
+    None,
+
+    /// Exponential moving average. Each sample moves the output `alpha` of the way towards it,
+    /// so lower is smoother but lags more.
+    Ema { alpha: f32 },
+
+    /// The 1€ filter: an exponential moving average whose cutoff rises with speed, so slow,
+    /// precise movements are smoothed heavily and fast ones barely lag.
+    OneEuro {
+        /// Cutoff frequency while still, in Hz. Lower removes more jitter.
+        min_cutoff: f32,
+
+        /// How much the cutoff rises per unit per second of speed. Higher lags less when fast.
+        beta: f32,
+
+        /// Cutoff frequency for the speed estimate, in Hz
+        derivative_cutoff: f32,
+    },
+}
+
+impl FilterConfig {
+    pub fn one_euro() -> Self {
+        FilterConfig::OneEuro {
+            min_cutoff: 1.0,
+            beta: 0.5,
+            derivative_cutoff: 1.0,
+        }
+    }
+}
+
+/// Smoothing factor of a first-order low-pass filter with cutoff `cutoff` Hz, sampled every `dt`
+/// seconds
+fn alpha(cutoff: f32, dt: f32) -> f32 {
+    let tau = 1.0 / (2.0 * PI * cutoff);
+    1.0 / (1.0 + tau / dt)
+}
+
+pub struct Filter {
+    config: FilterConfig,
+    last: Option<(Duration, f32)>,
+    derivative: f32,
+}
+
+impl Filter {
+    pub fn new(config: FilterConfig) -> Self {
+        Filter {
+            config,
+            last: None,
+            derivative: 0.0,
+        }
+    }
+
+    /// Filter a sample taken at `now`
+    pub fn filter(&mut self, value: f32, now: Duration) -> f32 {
+        let (last_at, last_value) = match self.last {
+            Some(last) => last,
+            None => {
+                self.last = Some((now, value));
+                return value;
+            }
+        };
+
+        let filtered = match self.config {
+            FilterConfig::None => value,
+            FilterConfig::Ema { alpha } => last_value + alpha * (value - last_value),
+            FilterConfig::OneEuro {
+                min_cutoff,
+                beta,
+                derivative_cutoff,
+            } => {
+                // Repeated timestamps would divide by zero
+                let dt = now
+                    .checked_sub(last_at)
+                    .unwrap_or_default()
+                    .as_secs_f32()
+                    .max(1e-4);
+                let derivative = (value - last_value) / dt;
+                self.derivative += alpha(derivative_cutoff, dt) * (derivative - self.derivative);
+                let cutoff = min_cutoff + beta * self.derivative.abs();
+                last_value + alpha(cutoff, dt) * (value - last_value)
+            }
+        };
+        self.last = Some((now, filtered));
+        filtered
+    }
+
+    /// Forget the signal's history, so the next sample passes straight through
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.derivative = 0.0;
+    }
+}
+
+/// Filters for each axis of absolute touches
+#[derive(Clone, Copy)]
+pub struct TouchFilterConfig {
+    pub x: FilterConfig,
+    pub y: FilterConfig,
+    pub z: FilterConfig,
+}
+
+impl Default for TouchFilterConfig {
+    fn default() -> Self {
+        TouchFilterConfig {
+            x: FilterConfig::one_euro(),
+            y: FilterConfig::one_euro(),
+            z: FilterConfig::None,
+        }
+    }
+}
+
+pub struct TouchFilter {
+    x: Filter,
+    y: Filter,
+    z: Filter,
+}
+
+impl TouchFilter {
+    pub fn new(config: TouchFilterConfig) -> Self {
+        TouchFilter {
+            x: Filter::new(config.x),
+            y: Filter::new(config.y),
+            z: Filter::new(config.z),
+        }
+    }
+
+    /// Smooth an absolute touch. Each new touch starts fresh, so it doesn't glide over from where
+    /// the last one lifted.
+    pub fn filter(&mut self, touch: Touch, now: Duration) -> Touch {
+        match touch {
+            Touch::Touched(x, y, z) => Touch::Touched(
+                self.x.filter(x, now),
+                self.y.filter(y, now),
+                self.z.filter(z, now),
+            ),
+            Touch::NotTouched => {
+                self.x.reset();
+                self.y.reset();
+                self.z.reset();
+                touch
+            }
+            Touch::Moved(_) => touch,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    /// Noise alternating around zero, sampled every 10 ms. Returns the largest output.
+    fn jitter(filter: &mut Filter, amplitude: f32) -> f32 {
+        (0..100)
+            .map(|sample| {
+                let value = if sample % 2 == 0 {
+                    amplitude
+                } else {
+                    -amplitude
+                };
+                filter.filter(value, ms(sample * 10)).abs()
+            })
+            .skip(10)
+            .fold(0.0, f32::max)
+    }
+
+    #[test]
+    fn none_passes_through() {
+        let mut filter = Filter::new(FilterConfig::None);
+        assert_eq!(filter.filter(0.5, ms(0)), 0.5);
+        assert_eq!(filter.filter(-0.25, ms(10)), -0.25);
+    }
+
+    #[test]
+    fn ema_moves_partway() {
+        let mut filter = Filter::new(FilterConfig::Ema { alpha: 0.25 });
+        assert_eq!(filter.filter(0.0, ms(0)), 0.0);
+        assert_eq!(filter.filter(1.0, ms(10)), 0.25);
+        assert_eq!(filter.filter(1.0, ms(20)), 0.4375);
+    }
+
+    #[test]
+    fn one_euro_smooths_jitter() {
+        let mut filter = Filter::new(FilterConfig::one_euro());
+        assert!(jitter(&mut filter, 0.01) < 0.006);
+    }
+
+    /// How far the filter trails a ramp at 5 units per second, sampled every 10 ms
+    fn ramp_lag(filter: &mut Filter) -> f32 {
+        (0..20)
+            .map(|sample| {
+                let value = sample as f32 * 0.05;
+                value - filter.filter(value, ms(sample * 10))
+            })
+            .last()
+            .unwrap()
+    }
+
+    #[test]
+    fn one_euro_follows_fast_movement() {
+        let mut filter = Filter::new(FilterConfig::one_euro());
+        let lag = ramp_lag(&mut filter);
+
+        // Without the speed term, it's a plain low-pass filter at the minimum cutoff
+        let mut low_pass = Filter::new(FilterConfig::OneEuro {
+            min_cutoff: 1.0,
+            beta: 0.0,
+            derivative_cutoff: 1.0,
+        });
+        assert!(lag > 0.0 && lag < ramp_lag(&mut low_pass) / 2.0);
+
+        // Repeated timestamps don't blow up
+        assert!(filter.filter(1.0, ms(190)).is_finite());
+    }
+
+    #[test]
+    fn new_touch_starts_fresh() {
+        let mut filter = TouchFilter::new(TouchFilterConfig::default());
+        filter.filter(Touch::Touched(-0.5, -0.5, 0.5), ms(0));
+        filter.filter(Touch::NotTouched, ms(10));
+        match filter.filter(Touch::Touched(0.5, 0.5, 0.5), ms(20)) {
+            Touch::Touched(x, y, _) => assert_eq!((x, y), (0.5, 0.5)),
+            _ => panic!("Expected a touch"),
+        }
+    }
+}
@@ -0,0 +1,194 @@
+//! Joystick emulation: turns a touch into a virtual analog stick
+
+use libm::sqrtf;
+
+use crate::session::Event;
+
+/// Where the stick's centre is
+#[derive(Clone, Copy)]
+pub enum Anchor {
+    /// The centre of the pad
+    Center,
+
+    /// Wherever the finger first touched, with full deflection `travel` away from it
+    FirstTouch { travel: f32 },
+}
+
+pub struct JoystickConfig {
+    pub anchor: Anchor,
+
+    /// Touches less than this fraction of the full travel from the stick's centre read as centred
+    pub deadzone: f32,
+
+    /// Touches at least this fraction of the full travel from the stick's centre deflect fully,
+    /// for running
+    pub outer_ring: f32,
+}
+
+impl Default for JoystickConfig {
+    fn default() -> Self {
+        JoystickConfig {
+            anchor: Anchor::Center,
+            deadzone: 0.1,
+            outer_ring: 0.9,
+        }
+    }
+}
+
+pub struct Joystick {
+    config: JoystickConfig,
+    origin: Option<(f32, f32)>,
+    deflection: (f32, f32),
+}
+
+impl Joystick {
+    pub fn new(config: JoystickConfig) -> Self {
+        Joystick {
+            config,
+            origin: None,
+            deflection: (0.0, 0.0),
+        }
+    }
+
+    /// Returns the stick deflection after this event, with magnitude up to 1
+    pub fn update(&mut self, event: &Event) -> (f32, f32) {
+        match *event {
+            Event::TouchDown { x, y, .. } | Event::Move { x, y, .. } => {
+                let origin = *self.origin.get_or_insert(match self.config.anchor {
+                    Anchor::Center => (0.0, 0.0),
+                    Anchor::FirstTouch { .. } => (x, y),
+                });
+                self.deflection = self.deflect(x, y, origin);
+            }
+            Event::Lift { .. } | Event::Hover { .. } => {
+                self.origin = None;
+                self.deflection = (0.0, 0.0);
+            }
+        }
+        self.deflection
+    }
+
+    fn deflect(&self, x: f32, y: f32, (origin_x, origin_y): (f32, f32)) -> (f32, f32) {
+        let (dx, dy) = (x - origin_x, y - origin_y);
+        let distance = sqrtf(dx * dx + dy * dy);
+        if distance == 0.0 {
+            return (0.0, 0.0);
+        }
+
+        let travel = match self.config.anchor {
+            Anchor::Center => 1.0,
+            Anchor::FirstTouch { travel } => travel,
+        };
+        let deadzone = self.config.deadzone * travel;
+        let magnitude = if distance >= self.config.outer_ring * travel {
+            1.0
+        } else if distance < deadzone {
+            0.0
+        } else if travel <= deadzone {
+            // No travel left past the deadzone, so leaving it deflects fully
+            1.0
+        } else {
+            ((distance - deadzone) / (travel - deadzone)).clamp(0.0, 1.0)
+        };
+
+        (dx / distance * magnitude, dy / distance * magnitude)
+    }
+
+    pub fn deflection(&self) -> (f32, f32) {
+        self.deflection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::time::Duration;
+
+    fn touch(x: f32, y: f32) -> Event {
+        Event::Move {
+            at: Duration::default(),
+            x,
+            y,
+            z: 0.5,
+            duration: Duration::default(),
+        }
+    }
+
+    fn magnitude((x, y): (f32, f32)) -> f32 {
+        sqrtf(x * x + y * y)
+    }
+
+    #[test]
+    fn centred_stick() {
+        let mut joystick = Joystick::new(JoystickConfig::default());
+        assert_eq!(joystick.update(&touch(0.05, 0.0)), (0.0, 0.0));
+
+        let (x, y) = joystick.update(&touch(0.0, -0.55));
+        assert!(x.abs() < 1e-6 && (y + 0.5).abs() < 1e-6);
+
+        // The outer ring runs
+        assert_eq!(joystick.update(&touch(0.0, 0.9)), (0.0, 1.0));
+    }
+
+    #[test]
+    fn first_touch_stick() {
+        let config = JoystickConfig {
+            anchor: Anchor::FirstTouch { travel: 0.5 },
+            ..Default::default()
+        };
+        let mut joystick = Joystick::new(config);
+        assert_eq!(joystick.update(&touch(0.8, 0.0)), (0.0, 0.0));
+
+        // Near the edge of the pad, but inside the deadzone around the first touch
+        let (x, _) = joystick.update(&touch(0.84, 0.0));
+        assert!(x.abs() < 1e-6);
+
+        // The deadzone scales with the travel, to 0.05 here
+        let (x, _) = joystick.update(&touch(0.525, 0.0));
+        assert!((x + 0.5).abs() < 1e-6);
+
+        // The outer ring is measured from the first touch too
+        assert_eq!(joystick.update(&touch(0.8, -0.45)), (0.0, -1.0));
+
+        // Lifting recentres, and the next touch is a new origin
+        joystick.update(&Event::Lift {
+            at: Duration::default(),
+            x: 0.8,
+            y: -0.45,
+            duration: Duration::default(),
+        });
+        assert_eq!(joystick.deflection(), (0.0, 0.0));
+        assert_eq!(joystick.update(&touch(-0.3, 0.3)), (0.0, 0.0));
+    }
+
+    #[test]
+    fn deadzone_covering_travel() {
+        // All the travel is deadzone, which would otherwise divide zero by zero at its edge
+        let config = JoystickConfig {
+            anchor: Anchor::FirstTouch { travel: 0.5 },
+            deadzone: 1.0,
+            outer_ring: 2.0,
+        };
+        let mut joystick = Joystick::new(config);
+        joystick.update(&touch(0.0, 0.0));
+        assert_eq!(joystick.update(&touch(0.25, 0.0)), (0.0, 0.0));
+        assert_eq!(joystick.update(&touch(0.5, 0.0)), (1.0, 0.0));
+    }
+
+    #[test]
+    fn magnitude_stays_in_range() {
+        // Deadzone past the travel would otherwise give negative deflection
+        let config = JoystickConfig {
+            anchor: Anchor::FirstTouch { travel: 0.05 },
+            deadzone: 1.5,
+            outer_ring: 2.0,
+        };
+        let mut joystick = Joystick::new(config);
+        joystick.update(&touch(0.0, 0.0));
+        for &(x, y) in &[(0.02, 0.0), (0.2, 0.0), (0.0, -0.6)] {
+            let deflection = joystick.update(&touch(x, y));
+            assert!((0.0..=1.0).contains(&magnitude(deflection)));
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! Trackpad input processing on top of the `tm035035` driver. Everything here works on
+//! timestamped [`Touch`](tm035035::Touch) packets, and doesn't touch hardware.
+
+#![no_std]
+
+pub mod click;
+pub mod filter;
+pub mod joystick;
+pub mod pad;
+pub mod palm;
+pub mod radial;
+pub mod scroll;
+pub mod session;
+pub mod swipe;
+pub mod tap;
+pub mod touches;
+pub mod trackball;
+pub mod transform;
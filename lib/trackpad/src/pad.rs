@@ -0,0 +1,219 @@
+//! One whole trackpad: every processing stage, configured and chained together
+
+use core::time::Duration;
+
+use crate::click::{Click, ClickConfig};
+use crate::filter::{TouchFilter, TouchFilterConfig};
+use crate::joystick::{Joystick, JoystickConfig};
+use crate::palm::{PalmConfig, PalmFilter};
+use crate::radial::{RadialButton, RadialConfig, RadialMenu};
+use crate::scroll::{ScrollConfig, ScrollTick, ScrollWheel};
+use crate::session::{Event, Session, SessionConfig};
+use crate::swipe::{Swipe, SwipeConfig, SwipeDetector};
+use crate::tap::{TapConfig, TapDetector, Taps};
+use crate::trackball::{Trackball, TrackballConfig};
+use crate::transform::Transform;
+use tm035035::Touch;
+
+#[derive(Default)]
+pub struct PadConfig {
+    pub transform: Transform,
+    pub palm: PalmConfig,
+    pub filter: TouchFilterConfig,
+    pub session: SessionConfig,
+    pub click: ClickConfig,
+    pub mode: PadMode,
+
+    /// Swipe gestures work alongside any mode
+    pub swipe: Option<SwipeConfig>,
+
+    /// So do taps
+    pub tap: Option<TapConfig>,
+}
+
+/// What a pad does besides reporting where it's touched
+#[derive(Default)]
+pub enum PadMode {
+    #[default]
+    Position,
+
+    /// Acts as an analog stick
+    Joystick(JoystickConfig),
+
+    /// Acts as a trackball mouse
+    Trackball(TrackballConfig),
+
+    /// Scrolls as the finger circles the pad
+    ScrollWheel(ScrollConfig),
+
+    /// Splits the pad into virtual buttons
+    Radial(RadialConfig),
+}
+
+enum Mode {
+    Position,
+    Joystick(Joystick),
+    Trackball(Trackball),
+    ScrollWheel(ScrollWheel),
+    Radial(RadialMenu),
+}
+
+impl From<PadMode> for Mode {
+    fn from(mode: PadMode) -> Self {
+        match mode {
+            PadMode::Position => Mode::Position,
+            PadMode::Joystick(config) => Mode::Joystick(Joystick::new(config)),
+            PadMode::Trackball(config) => Mode::Trackball(Trackball::new(config)),
+            PadMode::ScrollWheel(config) => Mode::ScrollWheel(ScrollWheel::new(config)),
+            PadMode::Radial(config) => Mode::Radial(RadialMenu::new(config)),
+        }
+    }
+}
+
+/// One trackpad, and the state of everything built on top of its touches
+pub struct Pad<T> {
+    touches: T,
+    transform: Transform,
+    palm: PalmFilter,
+    filter: TouchFilter,
+    session: Session,
+    click: Click,
+    mode: Mode,
+    mouse: (i16, i16),
+    scroll: ScrollTick,
+    swipe_detector: Option<SwipeDetector>,
+    swipe: Swipe,
+    radial: Option<RadialButton>,
+    tap_detector: Option<TapDetector>,
+    taps: Taps,
+}
+
+impl<T: Iterator<Item = Touch>> Pad<T> {
+    /// `touches` yields a packet whenever the pad has new data, and `None` otherwise
+    pub fn new(touches: T, config: PadConfig) -> Self {
+        Pad {
+            touches,
+            transform: config.transform,
+            palm: PalmFilter::new(config.palm),
+            filter: TouchFilter::new(config.filter),
+            session: Session::new(config.session),
+            click: Click::new(config.click),
+            mode: config.mode.into(),
+            mouse: (0, 0),
+            scroll: ScrollTick::default(),
+            swipe_detector: config.swipe.map(SwipeDetector::new),
+            swipe: Swipe::default(),
+            radial: None,
+            tap_detector: config.tap.map(TapDetector::new),
+            taps: Taps::default(),
+        }
+    }
+
+    /// Call once per frame. Reads the latest packet, if there is one.
+    pub fn update(&mut self, now: Duration) -> Option<Event> {
+        let event = self.read(now);
+        if let Some(event) = &event {
+            self.click.update(event);
+            match &mut self.mode {
+                Mode::Position => (),
+                Mode::Joystick(joystick) => {
+                    joystick.update(event);
+                }
+                Mode::Trackball(trackball) => trackball.update(event),
+                Mode::ScrollWheel(scroll_wheel) => scroll_wheel.update(event),
+                Mode::Radial(radial_menu) => radial_menu.update(event, now),
+            }
+            if let Some(swipe_detector) = &mut self.swipe_detector {
+                swipe_detector.update(event, now);
+            }
+            if let Some(tap_detector) = &mut self.tap_detector {
+                tap_detector.update(event, now);
+            }
+        }
+
+        self.mouse = match &mut self.mode {
+            // The ball keeps rolling between packets
+            Mode::Trackball(trackball) => trackball.tick(now),
+            _ => (0, 0),
+        };
+        self.scroll = match &mut self.mode {
+            Mode::ScrollWheel(scroll_wheel) => scroll_wheel.tick(),
+            _ => ScrollTick::default(),
+        };
+        self.radial = match &self.mode {
+            Mode::Radial(radial_menu) => radial_menu.pressed(now, self.click.is_pressed()),
+            _ => None,
+        };
+        self.swipe = self
+            .swipe_detector
+            .as_ref()
+            .map(|swipe_detector| swipe_detector.swipe(now))
+            .unwrap_or_default();
+        self.taps = self
+            .tap_detector
+            .as_ref()
+            .map(|tap_detector| tap_detector.taps(now))
+            .unwrap_or_default();
+
+        event
+    }
+
+    fn read(&mut self, now: Duration) -> Option<Event> {
+        let touch = self.transform.apply_touch(self.touches.next()?);
+        let touch = self.palm.filter(touch, now).unwrap_or_else(|rejection| {
+            log::debug!("Rejected touch: {}", rejection);
+            Touch::NotTouched
+        });
+        // After palm rejection, so big jumps are caught before they're smoothed over
+        let touch = self.filter.filter(touch, now);
+        self.session.update(&touch, now)
+    }
+
+    /// Where the pad is being touched, if it is
+    pub fn position(&self) -> Option<(f32, f32)> {
+        if self.session.is_touching() {
+            self.session.last_position()
+        } else {
+            None
+        }
+    }
+
+    /// Whether the pad is pressed hard enough to click
+    pub fn clicked(&self) -> bool {
+        self.click.is_pressed()
+    }
+
+    /// Virtual stick deflection if the pad is acting as a joystick, and otherwise where it's
+    /// touched, so protocols built around sticks still get something to steer with
+    pub fn stick(&self) -> (f32, f32) {
+        match &self.mode {
+            Mode::Joystick(joystick) => joystick.deflection(),
+            _ => self.position().unwrap_or_default(),
+        }
+    }
+
+    /// Mouse counts moved this frame, if the pad is acting as a trackball
+    pub fn mouse(&self) -> (i16, i16) {
+        self.mouse
+    }
+
+    /// Scroll ticks due this frame, if the pad is acting as a scroll wheel
+    pub fn scroll(&self) -> ScrollTick {
+        self.scroll
+    }
+
+    /// Virtual buttons pressed by a recent swipe
+    pub fn swipe(&self) -> Swipe {
+        self.swipe
+    }
+
+    /// Virtual button pressed, if the pad is acting as a radial menu
+    pub fn radial(&self) -> Option<RadialButton> {
+        self.radial
+    }
+
+    /// Virtual buttons pressed by recent taps
+    pub fn taps(&self) -> Taps {
+        self.taps
+    }
+}
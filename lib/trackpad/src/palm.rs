@@ -0,0 +1,206 @@
+//! Palm rejection: drops contacts that are more likely the base of a thumb than a fingertip
+
+use core::fmt;
+use core::time::Duration;
+
+use libm::sqrtf;
+use tm035035::Touch;
+
+pub struct PalmConfig {
+    /// Touches with Z above this are too big to be a fingertip
+    pub max_z: f32,
+
+    /// Touches further than this from the previous one are a different contact
+    pub max_jump: f32,
+
+    /// Distance from the centre at which a touch is at the edge
+    pub edge_radius: f32,
+
+    /// How far a touch at the edge can move and still count as resting
+    pub rest_tolerance: f32,
+
+    /// How long a touch can rest at the edge before it's rejected
+    pub max_edge_rest: Duration,
+}
+
+impl Default for PalmConfig {
+    fn default() -> Self {
+        PalmConfig {
+            max_z: 0.9,
+            max_jump: 0.5,
+            edge_radius: 0.85,
+            rest_tolerance: 0.05,
+            max_edge_rest: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Why a contact was rejected
+#[derive(Clone, Copy)]
+pub enum Rejection {
+    /// Z was over the ceiling
+    Pressure(f32),
+
+    /// The touch moved this far in one packet
+    Jump(f32),
+
+    /// The touch rested at the edge this long
+    Resting(Duration),
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Rejection::Pressure(z) => write!(formatter, "Z {:.02} over ceiling", z),
+            Rejection::Jump(distance) => write!(formatter, "jumped {:.02}", distance),
+            Rejection::Resting(duration) => {
+                write!(formatter, "rested at edge for {} ms", duration.as_millis())
+            }
+        }
+    }
+}
+
+pub struct PalmFilter {
+    config: PalmConfig,
+    last_position: Option<(f32, f32)>,
+    resting: Option<(Duration, (f32, f32))>,
+    rejected: Option<Rejection>,
+}
+
+impl PalmFilter {
+    pub fn new(config: PalmConfig) -> Self {
+        PalmFilter {
+            config,
+            last_position: None,
+            resting: None,
+            rejected: None,
+        }
+    }
+
+    /// Pass a touch through, or reject it. Once a contact is rejected, it stays rejected
+    /// until the pad reports no touch.
+    pub fn filter(&mut self, touch: Touch, now: Duration) -> Result<Touch, Rejection> {
+        let (x, y, z) = match touch {
+            Touch::Touched(x, y, z) => (x, y, z),
+            Touch::NotTouched => {
+                self.last_position = None;
+                self.resting = None;
+                self.rejected = None;
+                return Ok(touch);
+            }
+            Touch::Moved(_) => return Ok(touch),
+        };
+
+        if let Some(rejection) = self.rejected {
+            return Err(rejection);
+        }
+
+        let rejection = self.check(x, y, z, now);
+        self.last_position = Some((x, y));
+        match rejection {
+            Some(rejection) => {
+                self.rejected = Some(rejection);
+                Err(rejection)
+            }
+            None => Ok(touch),
+        }
+    }
+
+    fn check(&mut self, x: f32, y: f32, z: f32, now: Duration) -> Option<Rejection> {
+        if z > self.config.max_z {
+            return Some(Rejection::Pressure(z));
+        }
+
+        if let Some((last_x, last_y)) = self.last_position {
+            let jump_squared = (x - last_x) * (x - last_x) + (y - last_y) * (y - last_y);
+            if jump_squared > self.config.max_jump * self.config.max_jump {
+                return Some(Rejection::Jump(sqrtf(jump_squared)));
+            }
+        }
+
+        let edge = self.config.edge_radius;
+        if x * x + y * y < edge * edge {
+            self.resting = None;
+            return None;
+        }
+        let tolerance = self.config.rest_tolerance;
+        match self.resting {
+            Some((since, (rest_x, rest_y)))
+                if (x - rest_x) * (x - rest_x) + (y - rest_y) * (y - rest_y)
+                    <= tolerance * tolerance =>
+            {
+                let rested = now - since;
+                if rested > self.config.max_edge_rest {
+                    return Some(Rejection::Resting(rested));
+                }
+            }
+            _ => self.resting = Some((now, (x, y))),
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn fingertips_pass() {
+        let mut palm = PalmFilter::new(PalmConfig::default());
+        assert!(palm.filter(Touch::Touched(0.0, 0.0, 0.5), ms(0)).is_ok());
+        assert!(palm.filter(Touch::Touched(0.2, 0.1, 0.6), ms(10)).is_ok());
+    }
+
+    #[test]
+    fn heavy_contact_rejected_until_lift() {
+        let mut palm = PalmFilter::new(PalmConfig::default());
+        match palm.filter(Touch::Touched(0.0, 0.0, 0.95), ms(0)) {
+            Err(Rejection::Pressure(_)) => (),
+            _ => panic!("Expected a pressure rejection"),
+        }
+        // Still the same contact, however light it gets
+        match palm.filter(Touch::Touched(0.0, 0.0, 0.5), ms(10)) {
+            Err(Rejection::Pressure(_)) => (),
+            _ => panic!("Expected the rejection to stick"),
+        }
+
+        assert!(palm.filter(Touch::NotTouched, ms(20)).is_ok());
+        assert!(palm.filter(Touch::Touched(0.0, 0.0, 0.5), ms(30)).is_ok());
+    }
+
+    #[test]
+    fn jumps_rejected() {
+        let mut palm = PalmFilter::new(PalmConfig::default());
+        palm.filter(Touch::Touched(-0.4, 0.0, 0.5), ms(0)).ok();
+        match palm.filter(Touch::Touched(0.4, 0.0, 0.5), ms(10)) {
+            Err(Rejection::Jump(distance)) => assert!((distance - 0.8).abs() < 1e-6),
+            _ => panic!("Expected a jump rejection"),
+        }
+    }
+
+    #[test]
+    fn resting_at_edge_rejected() {
+        let mut palm = PalmFilter::new(PalmConfig::default());
+        assert!(palm.filter(Touch::Touched(0.9, 0.0, 0.5), ms(0)).is_ok());
+        assert!(palm.filter(Touch::Touched(0.91, 0.0, 0.5), ms(300)).is_ok());
+        assert!(palm.filter(Touch::Touched(0.9, 0.01, 0.5), ms(500)).is_ok());
+        match palm.filter(Touch::Touched(0.9, 0.0, 0.5), ms(510)) {
+            Err(Rejection::Resting(rested)) => assert_eq!(rested, ms(510)),
+            _ => panic!("Expected a resting rejection"),
+        }
+    }
+
+    #[test]
+    fn moving_along_edge_passes() {
+        let mut palm = PalmFilter::new(PalmConfig::default());
+        for step in 0..10 {
+            let y = step as f32 * 0.06;
+            let touch = Touch::Touched(0.9, y, 0.5);
+            assert!(palm.filter(touch, ms(step * 100)).is_ok());
+        }
+    }
+}
@@ -0,0 +1,209 @@
+//! Radial menus: splits the pad into angular segments, each a virtual button
+
+use core::f32::consts::PI;
+use core::time::Duration;
+
+use tm035035::geometry::polar;
+
+use crate::session::Event;
+
+/// When a touched segment counts as pressed
+#[derive(Clone, Copy)]
+pub enum Activation {
+    /// For as long as it's touched
+    Touch,
+
+    /// For as long as the pad is clicked
+    Click,
+
+    /// Briefly, when the finger lifts off it
+    Release,
+}
+
+pub struct RadialConfig {
+    /// Number of segments. Segment 0 is centred on north, up the pad towards -Y, and the rest
+    /// follow clockwise, so four segments are north, east, south and west like a d-pad.
+    pub segments: u8,
+
+    /// Touches closer to the centre than this don't select a segment
+    pub center_radius: f32,
+
+    /// Whether the centre is a button of its own
+    pub center_button: bool,
+
+    pub activation: Activation,
+
+    /// How long a segment stays pressed after release, with `Activation::Release`
+    pub press_time: Duration,
+}
+
+impl Default for RadialConfig {
+    fn default() -> Self {
+        RadialConfig {
+            segments: 4,
+            center_radius: 0.3,
+            center_button: false,
+            activation: Activation::Touch,
+            press_time: Duration::from_millis(50),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum RadialButton {
+    Segment(u8),
+    Center,
+}
+
+pub struct RadialMenu {
+    config: RadialConfig,
+    touched: Option<RadialButton>,
+    released: Option<RadialButton>,
+    released_until: Duration,
+}
+
+impl RadialMenu {
+    pub fn new(config: RadialConfig) -> Self {
+        RadialMenu {
+            config,
+            touched: None,
+            released: None,
+            released_until: Duration::ZERO,
+        }
+    }
+
+    /// `now` is when the event is handled. A release press is timed from then rather than from
+    /// the lift's last contact, which is a few packets earlier.
+    pub fn update(&mut self, event: &Event, now: Duration) {
+        match *event {
+            Event::TouchDown { x, y, .. } | Event::Move { x, y, .. } => {
+                self.touched = self.button_at(x, y);
+            }
+            Event::Lift { x, y, .. } => {
+                self.touched = None;
+                self.released = self.button_at(x, y);
+                self.released_until = now + self.config.press_time;
+            }
+            Event::Hover { .. } => self.touched = None,
+        }
+    }
+
+    fn button_at(&self, x: f32, y: f32) -> Option<RadialButton> {
+        let (radius, angle) = polar(x, y);
+        if radius < self.config.center_radius {
+            return if self.config.center_button {
+                Some(RadialButton::Center)
+            } else {
+                None
+            };
+        }
+
+        let segments = self.config.segments.max(1);
+        let width = 2.0 * PI / segments as f32;
+        // Clockwise from north, shifted half a segment so segment 0 straddles north,
+        // and a whole turn so it's never negative
+        let from_north = angle + PI / 2.0 + width / 2.0 + 2.0 * PI;
+        Some(RadialButton::Segment(
+            ((from_north / width) as u32 % segments as u32) as u8,
+        ))
+    }
+
+    /// The button pressed at `now`, given whether the pad is clicked
+    pub fn pressed(&self, now: Duration, clicked: bool) -> Option<RadialButton> {
+        match self.config.activation {
+            Activation::Touch => self.touched,
+            Activation::Click if clicked => self.touched,
+            Activation::Click => None,
+            Activation::Release if now < self.released_until => self.released,
+            Activation::Release => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn touch(menu: &mut RadialMenu, x: f32, y: f32) {
+        let event = Event::Move {
+            at: ms(0),
+            x,
+            y,
+            z: 0.5,
+            duration: ms(0),
+        };
+        menu.update(&event, ms(0));
+    }
+
+    fn lift(menu: &mut RadialMenu, x: f32, y: f32, at: u64, now: u64) {
+        let event = Event::Lift {
+            at: ms(at),
+            x,
+            y,
+            duration: ms(at),
+        };
+        menu.update(&event, ms(now));
+    }
+
+    #[test]
+    fn segments_clockwise_from_north() {
+        let mut menu = RadialMenu::new(RadialConfig::default());
+        let touches = [(0.0, -0.8, 0), (0.8, 0.0, 1), (0.0, 0.8, 2), (-0.8, 0.0, 3)];
+        for &(x, y, segment) in &touches {
+            touch(&mut menu, x, y);
+            assert!(menu.pressed(ms(0), false) == Some(RadialButton::Segment(segment)));
+        }
+
+        // Segment 0 straddles north
+        touch(&mut menu, -0.3, -0.7);
+        assert!(menu.pressed(ms(0), false) == Some(RadialButton::Segment(0)));
+    }
+
+    #[test]
+    fn centre() {
+        let mut menu = RadialMenu::new(RadialConfig::default());
+        touch(&mut menu, 0.1, 0.1);
+        assert!(menu.pressed(ms(0), false).is_none());
+
+        let config = RadialConfig {
+            center_button: true,
+            ..Default::default()
+        };
+        let mut menu = RadialMenu::new(config);
+        touch(&mut menu, 0.1, 0.1);
+        assert!(menu.pressed(ms(0), false) == Some(RadialButton::Center));
+    }
+
+    #[test]
+    fn click_activation() {
+        let config = RadialConfig {
+            activation: Activation::Click,
+            ..Default::default()
+        };
+        let mut menu = RadialMenu::new(config);
+        touch(&mut menu, 0.8, 0.0);
+        assert!(menu.pressed(ms(0), false).is_none());
+        assert!(menu.pressed(ms(0), true) == Some(RadialButton::Segment(1)));
+    }
+
+    #[test]
+    fn release_timed_from_handling() {
+        let config = RadialConfig {
+            activation: Activation::Release,
+            ..Default::default()
+        };
+        let mut menu = RadialMenu::new(config);
+        touch(&mut menu, 0.0, 0.8);
+        assert!(menu.pressed(ms(0), false).is_none());
+
+        // The lift is only recognized 60 ms after the last contact, past the whole press time
+        lift(&mut menu, 0.0, 0.8, 100, 160);
+        assert!(menu.pressed(ms(160), false) == Some(RadialButton::Segment(2)));
+        assert!(menu.pressed(ms(209), false) == Some(RadialButton::Segment(2)));
+        assert!(menu.pressed(ms(210), false).is_none());
+    }
+}
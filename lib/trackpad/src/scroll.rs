@@ -0,0 +1,233 @@
+//! Circular scrolling: turns a finger circling the pad into scroll wheel ticks, like a click wheel
+
+use core::f32::consts::PI;
+
+use tm035035::geometry::polar;
+
+use crate::session::Event;
+
+/// How scroll ticks are reported
+#[derive(Clone, Copy)]
+pub enum ScrollOutput {
+    /// As scroll wheel counts
+    Wheel,
+
+    /// As presses of virtual up and down buttons, one press per tick
+    Buttons,
+}
+
+pub struct ScrollConfig {
+    /// Angle the finger has to travel for each tick, in radians
+    pub step: f32,
+
+    /// Touches closer to the centre than this are ignored, since their angle is unstable
+    pub min_radius: f32,
+
+    /// Clockwise scrolls down by default (with +Y pointing down the pad). This flips it.
+    pub invert: bool,
+
+    pub output: ScrollOutput,
+}
+
+impl Default for ScrollConfig {
+    fn default() -> Self {
+        ScrollConfig {
+            step: PI / 8.0,
+            min_radius: 0.4,
+            invert: false,
+            output: ScrollOutput::Wheel,
+        }
+    }
+}
+
+/// Output for one frame
+#[derive(Clone, Copy, Default)]
+pub struct ScrollTick {
+    pub wheel: i8,
+    pub up: bool,
+    pub down: bool,
+}
+
+pub struct ScrollWheel {
+    config: ScrollConfig,
+    last_angle: Option<f32>,
+    travel: f32,
+    pending: i16,
+    button_held: bool,
+}
+
+impl ScrollWheel {
+    pub fn new(config: ScrollConfig) -> Self {
+        ScrollWheel {
+            config,
+            last_angle: None,
+            travel: 0.0,
+            pending: 0,
+            button_held: false,
+        }
+    }
+
+    pub fn update(&mut self, event: &Event) {
+        let (x, y) = match *event {
+            Event::TouchDown { x, y, .. } | Event::Move { x, y, .. } => (x, y),
+            Event::Lift { .. } | Event::Hover { .. } => {
+                self.last_angle = None;
+                self.travel = 0.0;
+                return;
+            }
+        };
+
+        let (radius, angle) = polar(x, y);
+        if radius < self.config.min_radius {
+            self.last_angle = None;
+            return;
+        }
+
+        if let Some(last_angle) = self.last_angle {
+            // Take the short way around, across the -π/π seam
+            let mut delta = angle - last_angle;
+            if delta > PI {
+                delta -= 2.0 * PI;
+            } else if delta < -PI {
+                delta += 2.0 * PI;
+            }
+            self.travel += delta;
+        }
+        self.last_angle = Some(angle);
+
+        let ticks = (self.travel / self.config.step) as i16;
+        self.travel -= ticks as f32 * self.config.step;
+        // Positive angle is clockwise, which scrolls down, and wheel counts are positive up
+        let ticks = if self.config.invert { ticks } else { -ticks };
+        self.pending = self.pending.saturating_add(ticks);
+    }
+
+    /// Call once per frame to take the ticks that are due
+    pub fn tick(&mut self) -> ScrollTick {
+        match self.config.output {
+            ScrollOutput::Wheel => {
+                let wheel = self.pending.max(i8::MIN.into()).min(i8::MAX.into()) as i8;
+                self.pending -= wheel as i16;
+                ScrollTick {
+                    wheel,
+                    ..Default::default()
+                }
+            }
+            ScrollOutput::Buttons => {
+                // Each tick is a press on one frame and a release on the next
+                if self.button_held || self.pending == 0 {
+                    self.button_held = false;
+                    return ScrollTick::default();
+                }
+                self.button_held = true;
+                let up = self.pending > 0;
+                self.pending -= self.pending.signum();
+                ScrollTick {
+                    up,
+                    down: !up,
+                    ..Default::default()
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::time::Duration;
+    use libm::{cosf, sinf};
+
+    /// A touch at `degrees` around a circle of `radius`
+    fn at_angle(radius: f32, degrees: f32) -> Event {
+        let angle = degrees.to_radians();
+        Event::Move {
+            at: Duration::default(),
+            x: radius * cosf(angle),
+            y: radius * sinf(angle),
+            z: 0.5,
+            duration: Duration::default(),
+        }
+    }
+
+    fn circle(wheel: &mut ScrollWheel, degrees: &[f32]) {
+        for &degrees in degrees {
+            wheel.update(&at_angle(0.8, degrees));
+        }
+    }
+
+    #[test]
+    fn clockwise_scrolls_down() {
+        let mut wheel = ScrollWheel::new(ScrollConfig::default());
+        circle(&mut wheel, &[0.0, 25.0, 50.0]);
+        assert_eq!(wheel.tick().wheel, -2);
+        assert_eq!(wheel.tick().wheel, 0);
+
+        // Back the other way, including the leftover 5°
+        circle(&mut wheel, &[25.0, 0.0, -25.0]);
+        assert_eq!(wheel.tick().wheel, 3);
+    }
+
+    #[test]
+    fn invert_flips() {
+        let config = ScrollConfig {
+            invert: true,
+            ..Default::default()
+        };
+        let mut wheel = ScrollWheel::new(config);
+        circle(&mut wheel, &[0.0, 25.0]);
+        assert_eq!(wheel.tick().wheel, 1);
+    }
+
+    #[test]
+    fn crossing_seam() {
+        let mut wheel = ScrollWheel::new(ScrollConfig::default());
+        circle(&mut wheel, &[170.0, -170.0]);
+        assert_eq!(wheel.tick().wheel, 0);
+        circle(&mut wheel, &[-145.0]);
+        assert_eq!(wheel.tick().wheel, -2);
+    }
+
+    #[test]
+    fn centre_and_lift_ignored() {
+        let mut wheel = ScrollWheel::new(ScrollConfig::default());
+        circle(&mut wheel, &[0.0]);
+        wheel.update(&at_angle(0.1, 90.0));
+        circle(&mut wheel, &[180.0]);
+        assert_eq!(wheel.tick().wheel, 0);
+
+        wheel.update(&Event::Lift {
+            at: Duration::default(),
+            x: -0.8,
+            y: 0.0,
+            duration: Duration::default(),
+        });
+        circle(&mut wheel, &[0.0]);
+        assert_eq!(wheel.tick().wheel, 0);
+    }
+
+    #[test]
+    fn buttons_press_once_per_tick() {
+        let config = ScrollConfig {
+            output: ScrollOutput::Buttons,
+            ..Default::default()
+        };
+        let mut wheel = ScrollWheel::new(config);
+        circle(&mut wheel, &[0.0, 25.0, 50.0]);
+        let presses: [(bool, bool); 5] = [(); 5].map(|_| {
+            let tick = wheel.tick();
+            (tick.up, tick.down)
+        });
+        assert_eq!(
+            presses,
+            [
+                (false, true),
+                (false, false),
+                (false, true),
+                (false, false),
+                (false, false)
+            ]
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Touch sessions: turns per-packet touches into touch-down, move and lift events
+
+use core::time::Duration;
+
+use tm035035::Touch;
+
+#[derive(Clone, Copy)]
+pub enum Event {
+    /// A finger made contact
+    TouchDown {
+        at: Duration,
+        x: f32,
+        y: f32,
+        z: f32,
+    },
+
+    /// A touching finger moved or changed pressure. `duration` is the time since touch-down.
+    Move {
+        at: Duration,
+        x: f32,
+        y: f32,
+        z: f32,
+        duration: Duration,
+    },
+
+    /// A finger left the pad. `at` is the time of the last contact, and `x` and `y` are where
+    /// it was last seen.
+    Lift {
+        at: Duration,
+        x: f32,
+        y: f32,
+        duration: Duration,
+    },
+
+    /// A finger is near the pad, but not touching it
+    Hover {
+        at: Duration,
+        x: f32,
+        y: f32,
+        z: f32,
+    },
+}
+
+impl Event {
+    pub fn at(&self) -> Duration {
+        match *self {
+            Event::TouchDown { at, .. }
+            | Event::Move { at, .. }
+            | Event::Lift { at, .. }
+            | Event::Hover { at, .. } => at,
+        }
+    }
+}
+
+pub struct SessionConfig {
+    /// Touches with Z below this are hovering rather than touching
+    pub contact_z: f32,
+
+    /// Consecutive packets without contact before a touch counts as lifted.
+    /// Single Z idle packets in the middle of a touch are ignored.
+    pub lift_packets: u8,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        SessionConfig {
+            contact_z: 0.05,
+            lift_packets: 3,
+        }
+    }
+}
+
+pub struct Session {
+    config: SessionConfig,
+    touch_down: Option<Duration>,
+    last_contact: Duration,
+    last_position: Option<(f32, f32)>,
+    idle_packets: u8,
+}
+
+impl Session {
+    pub fn new(config: SessionConfig) -> Self {
+        Session {
+            config,
+            touch_down: None,
+            last_contact: Duration::default(),
+            last_position: None,
+            idle_packets: 0,
+        }
+    }
+
+    pub fn update(&mut self, touch: &Touch, now: Duration) -> Option<Event> {
+        match *touch {
+            Touch::Touched(x, y, z) if z >= self.config.contact_z => {
+                self.idle_packets = 0;
+                self.last_contact = now;
+                self.last_position = Some((x, y));
+                Some(match self.touch_down {
+                    None => {
+                        self.touch_down = Some(now);
+                        Event::TouchDown { at: now, x, y, z }
+                    }
+                    Some(touch_down) => Event::Move {
+                        at: now,
+                        x,
+                        y,
+                        z,
+                        duration: now - touch_down,
+                    },
+                })
+            }
+            Touch::Touched(x, y, z) => match self.touch_down {
+                None => Some(Event::Hover { at: now, x, y, z }),
+                Some(_) => self.no_contact(),
+            },
+            Touch::NotTouched => self.no_contact(),
+            // Relative packets have no position to track
+            Touch::Moved(_) => None,
+        }
+    }
+
+    fn no_contact(&mut self) -> Option<Event> {
+        let touch_down = self.touch_down?;
+        self.idle_packets = self.idle_packets.saturating_add(1);
+        if self.idle_packets < self.config.lift_packets {
+            return None;
+        }
+
+        self.touch_down = None;
+        self.idle_packets = 0;
+        let (x, y) = self.last_position?;
+        Some(Event::Lift {
+            at: self.last_contact,
+            x,
+            y,
+            duration: self.last_contact - touch_down,
+        })
+    }
+
+    pub fn is_touching(&self) -> bool {
+        self.touch_down.is_some()
+    }
+
+    /// Where the pad was last touched, even if it's since been lifted
+    pub fn last_position(&self) -> Option<(f32, f32)> {
+        self.last_position
+    }
+
+    /// Time since touch-down, if the pad is being touched
+    pub fn touch_duration(&self, now: Duration) -> Option<Duration> {
+        self.touch_down.map(|touch_down| now - touch_down)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn touch_moves_and_lifts() {
+        let mut session = Session::new(SessionConfig::default());
+
+        match session.update(&Touch::Touched(0.1, 0.2, 0.5), ms(10)) {
+            Some(Event::TouchDown { at, x, y, .. }) => {
+                assert_eq!(at, ms(10));
+                assert_eq!((x, y), (0.1, 0.2));
+            }
+            _ => panic!("Expected a touch-down"),
+        }
+        assert!(session.is_touching());
+
+        match session.update(&Touch::Touched(0.3, 0.4, 0.5), ms(30)) {
+            Some(Event::Move {
+                at, x, y, duration, ..
+            }) => {
+                assert_eq!(at, ms(30));
+                assert_eq!((x, y), (0.3, 0.4));
+                assert_eq!(duration, ms(20));
+            }
+            _ => panic!("Expected a move"),
+        }
+        assert_eq!(session.touch_duration(ms(40)), Some(ms(30)));
+
+        // Lifted only after `lift_packets` packets without contact
+        assert!(session.update(&Touch::NotTouched, ms(40)).is_none());
+        assert!(session.update(&Touch::NotTouched, ms(50)).is_none());
+        match session.update(&Touch::NotTouched, ms(60)) {
+            Some(Event::Lift { at, x, y, duration }) => {
+                assert_eq!(at, ms(30));
+                assert_eq!((x, y), (0.3, 0.4));
+                assert_eq!(duration, ms(20));
+            }
+            _ => panic!("Expected a lift"),
+        }
+        assert!(!session.is_touching());
+        assert_eq!(session.last_position(), Some((0.3, 0.4)));
+        assert!(session.update(&Touch::NotTouched, ms(70)).is_none());
+    }
+
+    #[test]
+    fn single_idle_packet_keeps_touch() {
+        let mut session = Session::new(SessionConfig::default());
+        session.update(&Touch::Touched(0.0, 0.0, 0.5), ms(0));
+        assert!(session.update(&Touch::NotTouched, ms(10)).is_none());
+        match session.update(&Touch::Touched(0.1, 0.0, 0.5), ms(20)) {
+            Some(Event::Move { duration, .. }) => assert_eq!(duration, ms(20)),
+            _ => panic!("Expected the touch to carry on"),
+        }
+
+        // The idle count starts over after contact
+        assert!(session.update(&Touch::NotTouched, ms(30)).is_none());
+        assert!(session.update(&Touch::NotTouched, ms(40)).is_none());
+        assert!(session.is_touching());
+    }
+
+    #[test]
+    fn light_touches_hover() {
+        let mut session = Session::new(SessionConfig::default());
+        match session.update(&Touch::Touched(0.5, -0.5, 0.01), ms(0)) {
+            Some(Event::Hover { x, y, .. }) => assert_eq!((x, y), (0.5, -0.5)),
+            _ => panic!("Expected a hover"),
+        }
+        assert!(!session.is_touching());
+        assert_eq!(session.last_position(), None);
+
+        // While touching, a light packet counts as no contact
+        session.update(&Touch::Touched(0.0, 0.0, 0.5), ms(10));
+        assert!(session
+            .update(&Touch::Touched(0.0, 0.0, 0.01), ms(20))
+            .is_none());
+        assert!(session.is_touching());
+    }
+}
@@ -0,0 +1,226 @@
+//! Swipe gestures: turns quick, straight strokes into momentary virtual button presses
+
+use core::f32::consts::{FRAC_PI_4, FRAC_PI_8, PI};
+use core::time::Duration;
+
+use tm035035::geometry::polar;
+
+use crate::session::Event;
+
+/// Which way a swipe can go
+#[derive(Clone, Copy)]
+pub enum SwipeDirections {
+    /// Only north, east, south and west
+    Four,
+
+    /// Diagonals too, which press both neighbouring buttons
+    Eight,
+}
+
+pub struct SwipeConfig {
+    /// Distance between touch-down and lift, in unit disk radii
+    pub min_distance: f32,
+
+    /// Average speed over the whole stroke, in unit disk radii per second
+    pub min_speed: f32,
+
+    pub directions: SwipeDirections,
+
+    /// How long the virtual button stays pressed after a swipe
+    pub press_time: Duration,
+}
+
+impl Default for SwipeConfig {
+    fn default() -> Self {
+        SwipeConfig {
+            min_distance: 0.6,
+            min_speed: 3.0,
+            directions: SwipeDirections::Four,
+            press_time: Duration::from_millis(50),
+        }
+    }
+}
+
+/// Virtual buttons pressed by a swipe. North is up the pad, towards -Y.
+#[derive(Clone, Copy, Default)]
+pub struct Swipe {
+    pub n: bool,
+    pub e: bool,
+    pub s: bool,
+    pub w: bool,
+}
+
+pub struct SwipeDetector {
+    config: SwipeConfig,
+    start: Option<(f32, f32)>,
+    swipe: Swipe,
+    pressed_until: Duration,
+}
+
+impl SwipeDetector {
+    pub fn new(config: SwipeConfig) -> Self {
+        SwipeDetector {
+            config,
+            start: None,
+            swipe: Swipe::default(),
+            pressed_until: Duration::ZERO,
+        }
+    }
+
+    /// `now` is when the event is handled. The press is timed from then rather than from the
+    /// lift's last contact, which is a few packets earlier.
+    pub fn update(&mut self, event: &Event, now: Duration) {
+        match *event {
+            Event::TouchDown { x, y, .. } => self.start = Some((x, y)),
+            Event::Lift { x, y, duration, .. } => {
+                if let Some((start_x, start_y)) = self.start.take() {
+                    if let Some(swipe) = self.recognize(x - start_x, y - start_y, duration) {
+                        self.swipe = swipe;
+                        self.pressed_until = now + self.config.press_time;
+                    }
+                }
+            }
+            Event::Move { .. } | Event::Hover { .. } => (),
+        }
+    }
+
+    fn recognize(&self, dx: f32, dy: f32, duration: Duration) -> Option<Swipe> {
+        let (distance, angle) = polar(dx, dy);
+        let speed = distance / duration.as_secs_f32().max(f32::EPSILON);
+        if distance < self.config.min_distance || speed < self.config.min_speed {
+            return None;
+        }
+
+        // Octant from east, going clockwise since +Y is south
+        let octant = match self.config.directions {
+            SwipeDirections::Four if dx.abs() > dy.abs() => {
+                if dx > 0.0 {
+                    0
+                } else {
+                    4
+                }
+            }
+            SwipeDirections::Four => {
+                if dy > 0.0 {
+                    2
+                } else {
+                    6
+                }
+            }
+            SwipeDirections::Eight => ((angle + PI + FRAC_PI_8) / FRAC_PI_4) as u8 % 8 + 4,
+        } % 8;
+
+        Some(Swipe {
+            e: matches!(octant, 7 | 0 | 1),
+            s: matches!(octant, 1..=3),
+            w: matches!(octant, 3..=5),
+            n: matches!(octant, 5..=7),
+        })
+    }
+
+    /// The virtual buttons pressed at `now`
+    pub fn swipe(&self, now: Duration) -> Swipe {
+        if now < self.pressed_until {
+            self.swipe
+        } else {
+            Swipe::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    /// Stroke from `start` to `end` over `duration` ms, with the lift handled at `lifted`
+    fn stroke(
+        detector: &mut SwipeDetector,
+        start: (f32, f32),
+        end: (f32, f32),
+        duration: u64,
+        lifted: u64,
+    ) {
+        detector.update(
+            &Event::TouchDown {
+                at: ms(0),
+                x: start.0,
+                y: start.1,
+                z: 0.5,
+            },
+            ms(0),
+        );
+        detector.update(
+            &Event::Lift {
+                at: ms(duration),
+                x: end.0,
+                y: end.1,
+                duration: ms(duration),
+            },
+            ms(lifted),
+        );
+    }
+
+    fn buttons(swipe: Swipe) -> (bool, bool, bool, bool) {
+        (swipe.n, swipe.e, swipe.s, swipe.w)
+    }
+
+    #[test]
+    fn four_directions() {
+        let mut detector = SwipeDetector::new(SwipeConfig::default());
+        let strokes = [
+            ((0.0, 0.4), (0.1, -0.4), (true, false, false, false)),
+            ((-0.4, 0.0), (0.4, 0.3), (false, true, false, false)),
+            ((0.0, -0.4), (0.0, 0.4), (false, false, true, false)),
+            ((0.4, 0.0), (-0.4, 0.0), (false, false, false, true)),
+        ];
+        for &(start, end, expected) in &strokes {
+            stroke(&mut detector, start, end, 100, 100);
+            assert_eq!(buttons(detector.swipe(ms(100))), expected);
+        }
+    }
+
+    #[test]
+    fn eight_directions() {
+        let config = SwipeConfig {
+            directions: SwipeDirections::Eight,
+            ..Default::default()
+        };
+        let mut detector = SwipeDetector::new(config);
+        stroke(&mut detector, (-0.4, -0.4), (0.4, 0.4), 100, 100);
+        assert_eq!(buttons(detector.swipe(ms(100))), (false, true, true, false));
+        stroke(&mut detector, (0.0, 0.4), (0.0, -0.4), 100, 100);
+        assert_eq!(
+            buttons(detector.swipe(ms(100))),
+            (true, false, false, false)
+        );
+    }
+
+    #[test]
+    fn short_or_slow_strokes_ignored() {
+        let mut detector = SwipeDetector::new(SwipeConfig::default());
+        stroke(&mut detector, (0.0, 0.0), (0.3, 0.0), 50, 50);
+        assert_eq!(
+            buttons(detector.swipe(ms(50))),
+            (false, false, false, false)
+        );
+        stroke(&mut detector, (-0.4, 0.0), (0.4, 0.0), 1000, 1000);
+        assert_eq!(
+            buttons(detector.swipe(ms(1000))),
+            (false, false, false, false)
+        );
+    }
+
+    #[test]
+    fn press_timed_from_handling() {
+        let mut detector = SwipeDetector::new(SwipeConfig::default());
+        // The lift is only recognized 60 ms after the last contact, past the whole press time
+        stroke(&mut detector, (-0.4, 0.0), (0.4, 0.0), 100, 160);
+        assert!(detector.swipe(ms(160)).e);
+        assert!(detector.swipe(ms(209)).e);
+        assert!(!detector.swipe(ms(210)).e);
+    }
+}
@@ -0,0 +1,197 @@
+//! Taps and double taps: turns short, still touches into momentary virtual button presses
+
+use core::time::Duration;
+
+use tm035035::geometry::polar;
+
+use crate::session::Event;
+
+pub struct TapConfig {
+    /// Touches held longer than this aren't taps
+    pub max_duration: Duration,
+
+    /// Touches that wander further than this from where they started aren't taps, in unit disk
+    /// radii
+    pub max_movement: f32,
+
+    /// A tap that touches down within this long of the last tap lifting is a double tap
+    pub double_tap_window: Duration,
+
+    /// How long the virtual buttons stay pressed
+    pub press_time: Duration,
+}
+
+impl Default for TapConfig {
+    fn default() -> Self {
+        TapConfig {
+            max_duration: Duration::from_millis(200),
+            max_movement: 0.1,
+            double_tap_window: Duration::from_millis(300),
+            press_time: Duration::from_millis(50),
+        }
+    }
+}
+
+/// Virtual buttons pressed by taps. The second tap of a double tap presses both.
+#[derive(Clone, Copy, Default)]
+pub struct Taps {
+    pub tap: bool,
+    pub double_tap: bool,
+}
+
+pub struct TapDetector {
+    config: TapConfig,
+    start: Option<(f32, f32)>,
+    moved: bool,
+    last_tap: Option<Duration>,
+    tap_until: Duration,
+    double_tap_until: Duration,
+}
+
+impl TapDetector {
+    pub fn new(config: TapConfig) -> Self {
+        TapDetector {
+            config,
+            start: None,
+            moved: false,
+            last_tap: None,
+            tap_until: Duration::ZERO,
+            double_tap_until: Duration::ZERO,
+        }
+    }
+
+    /// `now` is when the event is handled. Presses are timed from then rather than from the
+    /// lift's last contact, which is a few packets earlier.
+    pub fn update(&mut self, event: &Event, now: Duration) {
+        match *event {
+            Event::TouchDown { x, y, .. } => {
+                self.start = Some((x, y));
+                self.moved = false;
+            }
+            Event::Move { x, y, .. } => {
+                if let Some((start_x, start_y)) = self.start {
+                    let (distance, _) = polar(x - start_x, y - start_y);
+                    self.moved |= distance > self.config.max_movement;
+                }
+            }
+            Event::Lift { at, duration, .. } => {
+                if self.start.take().is_none() || self.moved || duration > self.config.max_duration
+                {
+                    return;
+                }
+
+                self.tap_until = now + self.config.press_time;
+                let touch_down = at.checked_sub(duration).unwrap_or_default();
+                let gap = self
+                    .last_tap
+                    .and_then(|last_tap| touch_down.checked_sub(last_tap));
+                match gap {
+                    Some(gap) if gap <= self.config.double_tap_window => {
+                        self.double_tap_until = now + self.config.press_time;
+                        // A third tap starts over rather than double tapping again
+                        self.last_tap = None;
+                    }
+                    _ => self.last_tap = Some(at),
+                }
+            }
+            Event::Hover { .. } => (),
+        }
+    }
+
+    /// The virtual buttons pressed at `now`
+    pub fn taps(&self, now: Duration) -> Taps {
+        Taps {
+            tap: now < self.tap_until,
+            double_tap: now < self.double_tap_until,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    /// A touch from `down` to `up` ms that moves `distance`, with the lift handled at `lifted`
+    fn tap(detector: &mut TapDetector, down: u64, up: u64, distance: f32, lifted: u64) {
+        detector.update(
+            &Event::TouchDown {
+                at: ms(down),
+                x: 0.0,
+                y: 0.0,
+                z: 0.5,
+            },
+            ms(down),
+        );
+        detector.update(
+            &Event::Move {
+                at: ms(up),
+                x: distance,
+                y: 0.0,
+                z: 0.5,
+                duration: ms(up - down),
+            },
+            ms(up),
+        );
+        detector.update(
+            &Event::Lift {
+                at: ms(up),
+                x: distance,
+                y: 0.0,
+                duration: ms(up - down),
+            },
+            ms(lifted),
+        );
+    }
+
+    fn pressed(taps: Taps) -> (bool, bool) {
+        (taps.tap, taps.double_tap)
+    }
+
+    #[test]
+    fn single_tap() {
+        let mut detector = TapDetector::new(TapConfig::default());
+        tap(&mut detector, 0, 100, 0.0, 100);
+        assert_eq!(pressed(detector.taps(ms(100))), (true, false));
+        assert_eq!(pressed(detector.taps(ms(150))), (false, false));
+    }
+
+    #[test]
+    fn long_or_moving_touches_ignored() {
+        let mut detector = TapDetector::new(TapConfig::default());
+        tap(&mut detector, 0, 300, 0.0, 300);
+        assert_eq!(pressed(detector.taps(ms(300))), (false, false));
+        tap(&mut detector, 400, 450, 0.2, 450);
+        assert_eq!(pressed(detector.taps(ms(450))), (false, false));
+    }
+
+    #[test]
+    fn double_tap() {
+        let mut detector = TapDetector::new(TapConfig::default());
+        tap(&mut detector, 0, 100, 0.0, 100);
+        tap(&mut detector, 300, 400, 0.0, 400);
+        assert_eq!(pressed(detector.taps(ms(400))), (true, true));
+
+        // A third tap starts over
+        tap(&mut detector, 500, 600, 0.0, 600);
+        assert_eq!(pressed(detector.taps(ms(600))), (true, false));
+
+        // Too long after the last one
+        tap(&mut detector, 1000, 1100, 0.0, 1100);
+        assert_eq!(pressed(detector.taps(ms(1100))), (true, false));
+    }
+
+    #[test]
+    fn press_timed_from_handling() {
+        let mut detector = TapDetector::new(TapConfig::default());
+        tap(&mut detector, 0, 100, 0.0, 100);
+        // The lift is only recognized 60 ms after the last contact, past the whole press time
+        tap(&mut detector, 200, 250, 0.0, 310);
+        assert_eq!(pressed(detector.taps(ms(310))), (true, true));
+        assert_eq!(pressed(detector.taps(ms(359))), (true, true));
+        assert_eq!(pressed(detector.taps(ms(360))), (false, false));
+    }
+}
@@ -0,0 +1,43 @@
+//! Driver adapter: turns the `tm035035` driver's results into the plain touches a
+//! [`Pad`](crate::pad::Pad) reads
+
+use core::fmt::Debug;
+
+use tm035035::Touch;
+
+/// Touches from a pad driver, with read errors logged and dropped. A pad that isn't there never
+/// reports anything.
+pub struct Touches<I> {
+    driver: Option<I>,
+}
+
+impl<I> Touches<I> {
+    /// `driver` is `None` for a pad that didn't respond at startup
+    pub fn new(driver: Option<I>) -> Self {
+        Touches { driver }
+    }
+
+    pub fn driver(&mut self) -> Option<&mut I> {
+        self.driver.as_mut()
+    }
+}
+
+impl<I, E> Iterator for Touches<I>
+where
+    I: Iterator<Item = Result<Touch, E>>,
+    E: Debug,
+{
+    type Item = Touch;
+
+    /// The next packet, or `None` if there isn't one this frame. A failed read is skipped
+    /// like a missing packet, so one bad transfer doesn't end the pad's stream.
+    fn next(&mut self) -> Option<Touch> {
+        match self.driver.as_mut()?.next()? {
+            Ok(touch) => Some(touch),
+            Err(error) => {
+                log::warn!("Trackpad read failed: {:?}", error);
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! Trackball emulation: turns finger movement into mouse motion that keeps rolling after a flick
+
+use core::time::Duration;
+
+use libm::{expf, powf, sqrtf};
+
+use crate::session::Event;
+
+pub struct TrackballConfig {
+    /// Mouse counts per unit of finger travel at `reference_speed`
+    pub sensitivity: f32,
+
+    /// Exponent of the acceleration curve. 1 is linear, higher makes fast movements go further.
+    pub acceleration: f32,
+
+    /// Finger speed, in units per second, at which acceleration has no effect
+    pub reference_speed: f32,
+
+    /// How fast the ball slows while it coasts. Its speed decays by a factor of e
+    /// every `1 / friction` seconds.
+    pub friction: f32,
+
+    /// Slowest ball speed, in counts per second, that keeps rolling after lift
+    pub min_flick_speed: f32,
+}
+
+impl Default for TrackballConfig {
+    fn default() -> Self {
+        TrackballConfig {
+            sensitivity: 400.0,
+            acceleration: 1.5,
+            reference_speed: 2.0,
+            friction: 3.0,
+            min_flick_speed: 200.0,
+        }
+    }
+}
+
+pub struct Trackball {
+    config: TrackballConfig,
+    last_touch: Option<(Duration, f32, f32)>,
+    last_tick: Option<Duration>,
+    velocity: (f32, f32),
+    coasting: bool,
+    remainder: (f32, f32),
+}
+
+impl Trackball {
+    pub fn new(config: TrackballConfig) -> Self {
+        Trackball {
+            config,
+            last_touch: None,
+            last_tick: None,
+            velocity: (0.0, 0.0),
+            coasting: false,
+            remainder: (0.0, 0.0),
+        }
+    }
+
+    pub fn update(&mut self, event: &Event) {
+        match *event {
+            Event::TouchDown { at, x, y, .. } => {
+                // Touching the ball stops it
+                self.coasting = false;
+                self.velocity = (0.0, 0.0);
+                self.last_touch = Some((at, x, y));
+            }
+            Event::Move { at, x, y, .. } => {
+                if let Some((last_at, last_x, last_y)) = self.last_touch {
+                    self.roll(x - last_x, y - last_y, at - last_at);
+                }
+                self.last_touch = Some((at, x, y));
+            }
+            Event::Lift { .. } => {
+                self.last_touch = None;
+                let (vx, vy) = self.velocity;
+                self.coasting = sqrtf(vx * vx + vy * vy) >= self.config.min_flick_speed;
+            }
+            Event::Hover { .. } => (),
+        }
+    }
+
+    fn roll(&mut self, dx: f32, dy: f32, elapsed: Duration) {
+        let seconds = elapsed.as_secs_f32();
+        if seconds <= 0.0 {
+            return;
+        }
+
+        let speed = sqrtf(dx * dx + dy * dy) / seconds;
+        if speed == 0.0 {
+            self.velocity = (self.velocity.0 / 2.0, self.velocity.1 / 2.0);
+            return;
+        }
+
+        let gain = self.config.sensitivity
+            * powf(
+                speed / self.config.reference_speed,
+                self.config.acceleration - 1.0,
+            );
+        let (dx, dy) = (dx * gain, dy * gain);
+        self.remainder.0 += dx;
+        self.remainder.1 += dy;
+
+        // Smooth the velocity, so one jittery packet doesn't decide the flick
+        self.velocity = (
+            (self.velocity.0 + dx / seconds) / 2.0,
+            (self.velocity.1 + dy / seconds) / 2.0,
+        );
+    }
+
+    /// Call once per frame. Returns whole mouse counts moved since the last tick,
+    /// carrying the fraction over to the next.
+    pub fn tick(&mut self, now: Duration) -> (i16, i16) {
+        let elapsed = self
+            .last_tick
+            .map(|last_tick| (now - last_tick).as_secs_f32())
+            .unwrap_or_default();
+        self.last_tick = Some(now);
+
+        if self.coasting {
+            self.remainder.0 += self.velocity.0 * elapsed;
+            self.remainder.1 += self.velocity.1 * elapsed;
+
+            let decay = expf(-self.config.friction * elapsed);
+            self.velocity = (self.velocity.0 * decay, self.velocity.1 * decay);
+            let (vx, vy) = self.velocity;
+            // Stop before the ball crawls along a count at a time
+            self.coasting = sqrtf(vx * vx + vy * vy) >= self.config.min_flick_speed / 4.0;
+        }
+
+        let counts = (self.remainder.0 as i16, self.remainder.1 as i16);
+        self.remainder.0 -= counts.0 as f32;
+        self.remainder.1 -= counts.1 as f32;
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    fn touch_down(at: u64, x: f32) -> Event {
+        Event::TouchDown {
+            at: ms(at),
+            x,
+            y: 0.0,
+            z: 0.5,
+        }
+    }
+
+    fn moved(at: u64, x: f32) -> Event {
+        Event::Move {
+            at: ms(at),
+            x,
+            y: 0.0,
+            z: 0.5,
+            duration: ms(at),
+        }
+    }
+
+    fn lift(at: u64, x: f32) -> Event {
+        Event::Lift {
+            at: ms(at),
+            x,
+            y: 0.0,
+            duration: ms(at),
+        }
+    }
+
+    /// Drag along X at `step` per 10 ms packet, ticking with each one
+    fn drag(trackball: &mut Trackball, step: f32, packets: u64) -> i32 {
+        trackball.update(&touch_down(0, 0.0));
+        trackball.tick(ms(0));
+        (1..=packets)
+            .map(|packet| {
+                trackball.update(&moved(packet * 10, step * packet as f32));
+                trackball.tick(ms(packet * 10)).0 as i32
+            })
+            .sum()
+    }
+
+    #[test]
+    fn reference_speed_is_unaccelerated() {
+        let mut trackball = Trackball::new(TrackballConfig::default());
+        // 2 units per second, 0.1 units in all. Rounding can leave a fraction carried over.
+        assert!((39..=40).contains(&drag(&mut trackball, 0.02, 5)));
+    }
+
+    #[test]
+    fn fast_movement_goes_further() {
+        let mut trackball = Trackball::new(TrackballConfig::default());
+        // Same distance, four times as fast
+        assert!(drag(&mut trackball, 0.08, 1) > 40 * 3 / 2);
+    }
+
+    #[test]
+    fn flick_coasts_until_touched() {
+        let mut trackball = Trackball::new(TrackballConfig::default());
+        drag(&mut trackball, 0.05, 5);
+        trackball.update(&lift(50, 0.25));
+        let (coast, _) = trackball.tick(ms(60));
+        assert!(coast > 0);
+        // Slowing down
+        assert!(trackball.tick(ms(70)).0 <= coast);
+
+        trackball.update(&touch_down(80, 0.0));
+        trackball.tick(ms(80));
+        assert_eq!(trackball.tick(ms(90)), (0, 0));
+    }
+
+    #[test]
+    fn slow_lift_stops() {
+        let mut trackball = Trackball::new(TrackballConfig::default());
+        drag(&mut trackball, 0.001, 5);
+        trackball.update(&lift(50, 0.005));
+        assert_eq!(trackball.tick(ms(100)), (0, 0));
+    }
+
+    #[test]
+    fn coasting_winds_down() {
+        let mut trackball = Trackball::new(TrackballConfig::default());
+        drag(&mut trackball, 0.05, 5);
+        trackball.update(&lift(50, 0.25));
+        for frame in 6..300 {
+            trackball.tick(ms(frame * 10));
+        }
+        assert_eq!(trackball.tick(ms(3000)), (0, 0));
+    }
+}
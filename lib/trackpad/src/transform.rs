@@ -0,0 +1,147 @@
+//! Mounting transforms: turns pad coordinates into thumb coordinates for pads that are mounted
+//! rotated or mirrored
+
+use libm::{cosf, sinf};
+use tm035035::Touch;
+
+#[derive(Clone, Copy)]
+pub struct Transform {
+    swap_xy: bool,
+    invert_x: bool,
+    invert_y: bool,
+    rotation: f32,
+    sin: f32,
+    cos: f32,
+}
+
+impl Transform {
+    /// Axes are swapped first, then inverted, then rotated by `rotation` radians
+    /// from +X toward +Y.
+    pub fn new(rotation: f32, swap_xy: bool, invert_x: bool, invert_y: bool) -> Self {
+        Transform {
+            swap_xy,
+            invert_x,
+            invert_y,
+            rotation,
+            sin: sinf(rotation),
+            cos: cosf(rotation),
+        }
+    }
+
+    /// The same mounting, mirrored left to right, for the pad on the other side
+    pub fn mirrored(&self) -> Self {
+        Transform::new(-self.rotation, self.swap_xy, !self.invert_x, self.invert_y)
+    }
+
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        let (x, y) = if self.swap_xy { (y, x) } else { (x, y) };
+        let x = if self.invert_x { -x } else { x };
+        let y = if self.invert_y { -y } else { y };
+        (x * self.cos - y * self.sin, x * self.sin + y * self.cos)
+    }
+
+    pub fn apply_touch(&self, touch: Touch) -> Touch {
+        match touch {
+            Touch::Touched(x, y, z) => {
+                let (x, y) = self.apply(x, y);
+                Touch::Touched(x, y, z)
+            }
+            touch => touch,
+        }
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::new(0.0, false, false, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::f32::consts::PI;
+
+    const COS_15: f32 = 0.965_925_8;
+    const SIN_15: f32 = 0.258_819_04;
+
+    fn assert_near((x, y): (f32, f32), (expected_x, expected_y): (f32, f32)) {
+        assert!(
+            (x - expected_x).abs() < 1e-6 && (y - expected_y).abs() < 1e-6,
+            "({}, {}) isn't near ({}, {})",
+            x,
+            y,
+            expected_x,
+            expected_y
+        );
+    }
+
+    #[test]
+    fn identity() {
+        assert_near(Transform::default().apply(0.3, -0.4), (0.3, -0.4));
+    }
+
+    #[test]
+    fn rotation() {
+        let transform = Transform::new(15.0 * PI / 180.0, false, false, false);
+        assert_near(transform.apply(1.0, 0.0), (COS_15, SIN_15));
+        assert_near(transform.apply(0.0, 1.0), (-SIN_15, COS_15));
+        assert_near(transform.apply(0.0, 0.0), (0.0, 0.0));
+    }
+
+    #[test]
+    fn mirrored_rotation() {
+        let transform = Transform::new(15.0 * PI / 180.0, false, false, false).mirrored();
+        assert_near(transform.apply(1.0, 0.0), (-COS_15, SIN_15));
+        assert_near(transform.apply(0.0, 1.0), (SIN_15, COS_15));
+    }
+
+    #[test]
+    fn mirrored_twice_is_the_same() {
+        let transform = Transform::new(15.0 * PI / 180.0, true, false, true);
+        let twice = transform.mirrored().mirrored();
+        assert_near(twice.apply(0.3, -0.4), transform.apply(0.3, -0.4));
+    }
+
+    #[test]
+    fn swap_xy() {
+        let transform = Transform::new(0.0, true, false, false);
+        assert_near(transform.apply(0.3, -0.4), (-0.4, 0.3));
+    }
+
+    #[test]
+    fn invert_x() {
+        let transform = Transform::new(0.0, false, true, false);
+        assert_near(transform.apply(0.3, -0.4), (-0.3, -0.4));
+    }
+
+    #[test]
+    fn invert_y() {
+        let transform = Transform::new(0.0, false, false, true);
+        assert_near(transform.apply(0.3, -0.4), (0.3, 0.4));
+    }
+
+    #[test]
+    fn swap_then_invert() {
+        // Swapped first, so invert_x flips what came in as y
+        let transform = Transform::new(0.0, true, true, false);
+        assert_near(transform.apply(0.3, -0.4), (0.4, 0.3));
+    }
+
+    #[test]
+    fn touch_coordinates_only() {
+        let transform = Transform::new(0.0, false, true, false);
+        match transform.apply_touch(Touch::Touched(0.3, -0.4, 0.5)) {
+            Touch::Touched(x, y, z) => {
+                assert_near((x, y), (-0.3, -0.4));
+                assert_eq!(z, 0.5);
+            }
+            _ => panic!("Touch lost"),
+        }
+        assert!(matches!(
+            transform.apply_touch(Touch::NotTouched),
+            Touch::NotTouched
+        ));
+    }
+}
@@ -0,0 +1,83 @@
+//! Trackpad range calibration at power-on
+//!
+//! Holding the left pad at power-on starts calibration. Each pad present then gets a few seconds
+//! to have its edge traced, one after the other, and the traced ranges are stored.
+
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::spi::Transfer;
+use embedded_hal::digital::v2::InputPin;
+use tm035035::calibration::Calibration;
+use tm035035::geometry::Geometry;
+use tm035035::{Tm035035, Touch};
+
+use crate::storage::{Slot, Storage};
+
+/// How long to watch for a held pad at power-on
+const REQUEST_MS: u16 = 50;
+
+/// How long each pad's edge has to be traced for
+const TRACE_MS: u16 = 5000;
+
+/// Use the pad's stored calibration, if it has one
+pub fn restore<S, DR>(pad: &mut Tm035035<S, DR>, storage: &Storage, slot: Slot)
+where
+    S: Transfer<u8>,
+    DR: InputPin,
+{
+    let mut bytes = [0; 12];
+    if !storage.read(slot, &mut bytes) {
+        return;
+    }
+    if let Some(calibration) = Calibration::from_bytes(&bytes) {
+        let geometry = Geometry::calibrated(&calibration, pad.geometry().rim_width);
+        pad.set_geometry(geometry);
+    }
+}
+
+/// Whether the pad is being held, asking for calibration
+pub fn requested<S, DR, D>(pad: &mut Tm035035<S, DR>, delay: &mut D) -> bool
+where
+    S: Transfer<u8>,
+    DR: InputPin,
+    D: DelayMs<u8>,
+{
+    for _ in 0..REQUEST_MS {
+        if let Some(Ok(Touch::Touched(..))) = pad.next() {
+            return true;
+        }
+        delay.delay_ms(1);
+    }
+    false
+}
+
+/// Record the range while the user traces the pad's edge, then use and store it.
+/// A trace that doesn't cover the pad leaves the calibration as it was.
+pub fn calibrate<S, DR, D>(
+    name: &str,
+    pad: &mut Tm035035<S, DR>,
+    storage: &mut Storage,
+    slot: Slot,
+    delay: &mut D,
+) where
+    S: Transfer<u8>,
+    DR: InputPin,
+    D: DelayMs<u8>,
+{
+    log::info!("Trace the edge of the {} pad", name);
+    pad.start_range_calibration();
+    for _ in 0..TRACE_MS {
+        // Errors only lose a sample
+        pad.next();
+        delay.delay_ms(1);
+    }
+    match pad.finish_range_calibration() {
+        Some(calibration) => {
+            storage.write(slot, &calibration.to_bytes());
+            log::info!("Calibrated the {} pad", name);
+        }
+        None => log::warn!(
+            "The {} pad trace didn't reach the edge, not calibrated",
+            name
+        ),
+    }
+}
@@ -0,0 +1,37 @@
+//! Time since boot, for timestamping input
+
+use core::time::Duration;
+
+use bsp::hal::gpt::{Mode, GPT};
+use teensy4_bsp as bsp;
+
+pub struct Clock {
+    gpt: GPT,
+    last_count: u32,
+    ticks: u64,
+}
+
+impl Clock {
+    /// Start counting from zero on a free-running GPT
+    pub fn new(mut gpt: GPT) -> Self {
+        gpt.set_mode(Mode::FreeRunning);
+        gpt.set_reset_on_enable(true);
+        gpt.set_enable(true);
+        Clock {
+            gpt,
+            last_count: 0,
+            ticks: 0,
+        }
+    }
+
+    /// Time since the clock started
+    ///
+    /// The GPT counter wraps, so this needs to be called at least once per wrap
+    /// (a little over two hours at 2 µs per tick) to stay accurate.
+    pub fn now(&mut self) -> Duration {
+        let count = self.gpt.count();
+        self.ticks += count.wrapping_sub(self.last_count) as u64;
+        self.last_count = count;
+        Duration::from_nanos(self.ticks * self.gpt.clock_period().as_nanos() as u64)
+    }
+}
@@ -2,123 +2,68 @@ use embedded_hal::blocking::spi::Transfer;
 use embedded_hal::digital::v2::OutputPin;
 
 use lsm6ds33::Lsm6ds33;
+use tm035035::Touch;
+use trackpad::pad::Pad;
+use trackpad::radial::RadialButton;
 
-pub struct Frame {
-    pub left_quad_n: bool,
-    pub left_quad_e: bool,
-    pub left_quad_s: bool,
-    pub left_quad_w: bool,
+use crate::clock::Clock;
 
-    pub right_quad_n: bool,
-    pub right_quad_e: bool,
-    pub right_quad_s: bool,
-    pub right_quad_w: bool,
+pub use gamepad::frame::Frame;
 
-    pub left_pad_x: f32,
-    pub left_pad_y: f32,
-    pub left_pad_click: bool,
-
-    pub right_pad_x: f32,
-    pub right_pad_y: f32,
-    pub right_pad_click: bool,
-
-    pub home: bool,
-
-    pub select: bool,
-    pub start: bool,
-
-    pub left_bumper: bool,
-    pub right_bumper: bool,
-
-    pub left_trigger: f32,
-    pub right_trigger: f32,
-
-    pub left_grip: bool,
-    pub right_grip: bool,
-
-    pub accel_x: f32,
-    pub accel_y: f32,
-    pub accel_z: f32,
-
-    pub gyro_x: f32,
-    pub gyro_y: f32,
-    pub gyro_z: f32,
-
-    pub mag_x: f32,
-    pub mag_y: f32,
-    pub mag_z: f32,
-}
-
-impl core::fmt::Display for Frame {
-    fn fmt(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
-        formatter.write_fmt(format_args!(
-            "\nQuads: left(N {}, E {}, S {}, W {}) right(N {}, E {}, S {} W {})",
-            self.left_quad_n,
-            self.left_quad_e,
-            self.left_quad_s,
-            self.left_quad_w,
-            self.right_quad_n,
-            self.right_quad_e,
-            self.right_quad_s,
-            self.right_quad_w,
-        ))?;
-        formatter.write_fmt(format_args!(
-            "\nPads: left(X {:.02}, Y {:.02}, click {}) right(X {:.02}, Y {:.02}, click {})",
-            self.left_pad_x,
-            self.left_pad_y,
-            self.left_pad_click,
-            self.right_pad_x,
-            self.right_pad_y,
-            self.right_pad_click,
-        ))?;
-        formatter.write_fmt(format_args!(
-            "\nselect {}, home {}, start {}",
-            self.select, self.home, self.start,
-        ))?;
-        formatter.write_fmt(format_args!(
-            "\nBumpers: left {}, right {}",
-            self.left_bumper, self.right_bumper,
-        ))?;
-        formatter.write_fmt(format_args!(
-            "\nTriggers: left {}, right {}",
-            self.left_trigger, self.right_trigger,
-        ))?;
-        formatter.write_fmt(format_args!(
-            "\nGrips: left {}, right {}",
-            self.left_grip, self.right_grip,
-        ))?;
-        formatter.write_fmt(format_args!(
-            "\nAccel: X {}, Y {}, Z {}",
-            self.accel_x, self.accel_y, self.accel_z
-        ))?;
-        formatter.write_fmt(format_args!(
-            "\nGyro: X {}, Y {}, Z {}",
-            self.gyro_x, self.gyro_y, self.gyro_z
-        ))?;
-        formatter.write_fmt(format_args!(
-            "\nMag: X {}, Y {}, Z {}",
-            self.mag_x, self.mag_y, self.mag_z
-        ))?;
-        Ok(())
-    }
+pub struct Controller<S, C, L, R> {
+    imu: Lsm6ds33<S, C>,
+    left_pad: Pad<L>,
+    right_pad: Pad<R>,
+    clock: Clock,
 }
 
-pub struct Controller<S, C>(Lsm6ds33<S, C>);
-
-impl<S, C> Controller<S, C> {
-    pub fn new(imu: Lsm6ds33<S, C>) -> Self {
-        Controller(imu)
+impl<S, C, L, R> Controller<S, C, L, R> {
+    pub fn new(imu: Lsm6ds33<S, C>, left_pad: Pad<L>, right_pad: Pad<R>, clock: Clock) -> Self {
+        Controller {
+            imu,
+            left_pad,
+            right_pad,
+            clock,
+        }
     }
 }
 
-impl<S: Transfer<u8>, C: OutputPin> Iterator for Controller<S, C> {
+impl<S, C, L, R> Iterator for Controller<S, C, L, R>
+where
+    S: Transfer<u8>,
+    C: OutputPin,
+    L: Iterator<Item = Touch>,
+    R: Iterator<Item = Touch>,
+{
     type Item = Frame;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let imu_item = self.0.next();
+        let now = self.clock.now();
+        self.left_pad.update(now);
+        self.right_pad.update(now);
+        let (left_position, right_position) = (self.left_pad.position(), self.right_pad.position());
+        let (left_pad_x, left_pad_y) = left_position.unwrap_or_default();
+        let (right_pad_x, right_pad_y) = right_position.unwrap_or_default();
+        let (left_stick_x, left_stick_y) = self.left_pad.stick();
+        let (right_stick_x, right_stick_y) = self.right_pad.stick();
+        // Either pad can move the mouse
+        let (left_mouse_x, left_mouse_y) = self.left_pad.mouse();
+        let (right_mouse_x, right_mouse_y) = self.right_pad.mouse();
+        let (left_scroll, right_scroll) = (self.left_pad.scroll(), self.right_pad.scroll());
+        let (left_swipe, right_swipe) = (self.left_pad.swipe(), self.right_pad.swipe());
+        let (left_radial, right_radial) = (self.left_pad.radial(), self.right_pad.radial());
+        let (left_taps, right_taps) = (self.left_pad.taps(), self.right_pad.taps());
+        let segment = |radial: Option<RadialButton>| match radial {
+            Some(RadialButton::Segment(segment)) => Some(segment),
+            _ => None,
+        };
+
+        let imu_item = self.imu.next();
         let imu_result = imu_item?;
         if let Ok(((accel_x, accel_y, accel_z), (gyro_x, gyro_y, gyro_z))) = imu_result {
             Some(Frame {
+                time: now,
+
                 left_quad_n: false,
                 left_quad_e: false,
                 left_quad_s: false,
@@ -129,13 +74,50 @@ impl<S: Transfer<u8>, C: OutputPin> Iterator for Controller<S, C> {
                 right_quad_s: false,
                 right_quad_w: false,
 
-                left_pad_x: 0.0,
-                left_pad_y: 0.0,
-                left_pad_click: false,
+                left_pad_x,
+                left_pad_y,
+                left_pad_touched: left_position.is_some(),
+                left_pad_click: self.left_pad.clicked(),
+
+                right_pad_x,
+                right_pad_y,
+                right_pad_touched: right_position.is_some(),
+                right_pad_click: self.right_pad.clicked(),
+
+                left_swipe_n: left_swipe.n,
+                left_swipe_e: left_swipe.e,
+                left_swipe_s: left_swipe.s,
+                left_swipe_w: left_swipe.w,
+
+                right_swipe_n: right_swipe.n,
+                right_swipe_e: right_swipe.e,
+                right_swipe_s: right_swipe.s,
+                right_swipe_w: right_swipe.w,
+
+                left_radial_segment: segment(left_radial),
+                left_radial_center: left_radial == Some(RadialButton::Center),
+
+                right_radial_segment: segment(right_radial),
+                right_radial_center: right_radial == Some(RadialButton::Center),
+
+                left_tap: left_taps.tap,
+                left_double_tap: left_taps.double_tap,
+
+                right_tap: right_taps.tap,
+                right_double_tap: right_taps.double_tap,
+
+                left_stick_x,
+                left_stick_y,
+
+                right_stick_x,
+                right_stick_y,
+
+                mouse_x: left_mouse_x.saturating_add(right_mouse_x),
+                mouse_y: left_mouse_y.saturating_add(right_mouse_y),
 
-                right_pad_x: 0.0,
-                right_pad_y: 0.0,
-                right_pad_click: false,
+                wheel: left_scroll.wheel.saturating_add(right_scroll.wheel),
+                wheel_up: left_scroll.up || right_scroll.up,
+                wheel_down: left_scroll.down || right_scroll.down,
 
                 home: false,
 
@@ -0,0 +1,142 @@
+//! The two flash sectors just past the program, for [`eeprom`] to keep settings in
+//!
+//! The program runs straight out of this flash, so it can't be driven directly while anything
+//! else might be fetched from it. The i.MX RT boot ROM has a FlexSPI NOR driver for this, which
+//! runs from ROM. Calls into it are made with interrupts off.
+
+use core::cell::RefCell;
+
+/// Flash offset of the first sector. The linker script gives the program the first 1984 KiB.
+const BASE: u32 = 0x1f_0000;
+
+/// The boot ROM's NOR driver only programs whole pages
+const PAGE_LEN: usize = 256;
+
+/// FlexSPI instance the flash is on
+const INSTANCE: u32 = 1;
+
+/// Where the boot ROM keeps a pointer to its API tree
+const BOOTLOADER_TREE: *const *const BootloaderTree = 0x0020_001c as _;
+
+/// The flash configuration block the boot ROM booted from, at the start of flash
+const BOOT_CONFIG: *const NorConfig = 0x6000_0000 as _;
+
+const SUCCESS: i32 = 0;
+
+/// `flexspi_nor_config_t`, only ever copied whole from the boot configuration
+#[derive(Clone, Copy)]
+#[repr(C, align(4))]
+struct NorConfig([u8; 512]);
+
+#[repr(C)]
+struct BootloaderTree {
+    version: u32,
+    copyright: *const u8,
+    run_bootloader: extern "C" fn(*mut u8),
+    reserved: *const u32,
+    nor_driver: *const NorDriver,
+}
+
+#[repr(C)]
+struct NorDriver {
+    version: u32,
+    init: extern "C" fn(u32, *mut NorConfig) -> i32,
+    program: extern "C" fn(u32, *mut NorConfig, u32, *const u32) -> i32,
+    erase_all: extern "C" fn(u32, *mut NorConfig) -> i32,
+    erase: extern "C" fn(u32, *mut NorConfig, u32, u32) -> i32,
+    read: extern "C" fn(u32, *mut NorConfig, *mut u32, u32, u32) -> i32,
+    clear_cache: extern "C" fn(u32),
+}
+
+/// A page, aligned for the boot ROM's word accesses
+#[repr(C, align(4))]
+struct Page([u8; PAGE_LEN]);
+
+pub struct RomFlash {
+    driver: &'static NorDriver,
+    config: RefCell<NorConfig>,
+}
+
+impl RomFlash {
+    /// Set up the boot ROM's NOR driver with the configuration the chip booted with
+    pub fn new() -> Self {
+        // Safety: the boot ROM is always mapped, and the boot configuration is at the start of
+        // flash for the boot ROM to have booted at all
+        let (driver, config) = unsafe { (&*(**BOOTLOADER_TREE).nor_driver, *BOOT_CONFIG) };
+        let flash = RomFlash {
+            driver,
+            config: RefCell::new(config),
+        };
+        flash.call("init", |driver, config| (driver.init)(INSTANCE, config));
+        flash
+    }
+
+    /// Call into the boot ROM with interrupts off, then drop anything read ahead from flash
+    fn call(&self, name: &str, f: impl FnOnce(&NorDriver, *mut NorConfig) -> i32) {
+        let status = cortex_m::interrupt::free(|_| {
+            let status = f(self.driver, &mut *self.config.borrow_mut());
+            (self.driver.clear_cache)(INSTANCE);
+            status
+        });
+        if status != SUCCESS {
+            log::error!("Flash {} failed with status {}", name, status);
+        }
+    }
+
+    fn read_page(&self, page: u32, buffer: &mut Page) {
+        self.call("read", |driver, config| {
+            let dst = buffer.0.as_mut_ptr() as *mut u32;
+            (driver.read)(INSTANCE, config, dst, page, PAGE_LEN as u32)
+        });
+    }
+}
+
+impl Default for RomFlash {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl eeprom::Flash for RomFlash {
+    const SECTOR_LEN: usize = 4096;
+
+    fn read(&self, offset: usize, buffer: &mut [u8]) {
+        // Read whole pages, which keeps the boot ROM's accesses aligned
+        let mut page = Page([0; PAGE_LEN]);
+        let mut done = 0;
+        while done < buffer.len() {
+            let address = offset + done;
+            let start = address % PAGE_LEN;
+            let len = (PAGE_LEN - start).min(buffer.len() - done);
+            self.read_page(BASE + (address - start) as u32, &mut page);
+            buffer[done..done + len].copy_from_slice(&page.0[start..start + len]);
+            done += len;
+        }
+    }
+
+    fn erase(&mut self, sector: usize) {
+        let start = BASE + (sector * Self::SECTOR_LEN) as u32;
+        self.call("erase", |driver, config| {
+            (driver.erase)(INSTANCE, config, start, Self::SECTOR_LEN as u32)
+        });
+    }
+
+    fn program(&mut self, offset: usize, data: &[u8]) {
+        // Erased bytes program as no change, so the rest of each page is left as it is
+        let mut page = Page([0xff; PAGE_LEN]);
+        let mut done = 0;
+        while done < data.len() {
+            let address = offset + done;
+            let start = address % PAGE_LEN;
+            let len = (PAGE_LEN - start).min(data.len() - done);
+            page.0.fill(0xff);
+            page.0[start..start + len].copy_from_slice(&data[done..done + len]);
+            let page_address = BASE + (address - start) as u32;
+            self.call("program", |driver, config| {
+                let src = page.0.as_ptr() as *const u32;
+                (driver.program)(INSTANCE, config, page_address, src)
+            });
+            done += len;
+        }
+    }
+}
@@ -1,60 +1,123 @@
-//! USB logging support
+//! Logging support
 //!
-//! If you don't want USB logging, remove
+//! Log records are formatted into a buffer, which the USB device drains into its serial
+//! interface. When nothing is draining it, such as when the controller is emulating a console
+//! controller, output that doesn't fit is dropped.
 //!
-//! - this module
-//! - the `log` dependency in Cargo.toml
+//! How much gets logged is the level passed to [`init`]. Logging can be turned off there, but
+//! the module has to stay, since the USB device drains its buffer.
 
 use core::cell::RefCell;
+use core::fmt::Write;
 use cortex_m::interrupt::Mutex;
 
-use bsp::hal::ral::usb::USB1;
-use bsp::interrupt;
-use teensy4_bsp as bsp;
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
 
-/// Specify any logging filters here
-///
-/// See the BSP docs for more information
-/// on logging filters.
-// const FILTERS: &'static [bsp::usb::Filter] = &[("cfw", None)];
+/// Bytes waiting to be drained
+const BUFFER_LEN: usize = 4096;
 
-/// Initialize the USB logging system, and prepares the
-/// USB ISR with the poller
-///
-/// When `init` returns, the USB interrupt will be enabled,
-/// and the host may begin to interface the device.
-/// You should only call this once.
-///
-/// # Panics
-///
-/// Panics if the imxrt-ral USB1 instance is already taken.
-pub fn init() -> Result<bsp::usb::Reader, bsp::usb::Error> {
-    let inst = USB1::take().unwrap();
-    bsp::usb::init(inst, Default::default()).map(|(poller, reader)| {
-        setup(poller);
-        reader
-    })
+/// Longest formatted record, enough for a whole `Frame`. Longer records are cut short.
+const LINE_LEN: usize = 1024;
+
+/// A ring buffer of formatted log output
+struct Buffer {
+    bytes: [u8; BUFFER_LEN],
+    start: usize,
+    len: usize,
 }
 
-/// Setup the USB ISR with the USB poller
-fn setup(poller: bsp::usb::Poller) {
-    static POLLER: Mutex<RefCell<Option<bsp::usb::Poller>>> = Mutex::new(RefCell::new(None));
+impl Buffer {
+    /// Append as much of `bytes` as fits
+    fn push(&mut self, bytes: &[u8]) {
+        for &byte in bytes.iter().take(BUFFER_LEN - self.len) {
+            self.bytes[(self.start + self.len) % BUFFER_LEN] = byte;
+            self.len += 1;
+        }
+    }
+}
+
+/// One record, formatted before it goes into the buffer, so interrupts aren't held off while
+/// it's formatted
+struct Line {
+    bytes: [u8; LINE_LEN],
+    len: usize,
+}
+
+impl Write for Line {
+    /// Append as much of `s` as fits, leaving room for the line ending
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let taken = s.len().min(LINE_LEN - 2 - self.len);
+        self.bytes[self.len..self.len + taken].copy_from_slice(&s.as_bytes()[..taken]);
+        self.len += taken;
+        Ok(())
+    }
+}
+
+static BUFFER: Mutex<RefCell<Buffer>> = Mutex::new(RefCell::new(Buffer {
+    bytes: [0; BUFFER_LEN],
+    start: 0,
+    len: 0,
+}));
+
+struct Logger;
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let mut line = Line {
+            bytes: [0; LINE_LEN],
+            len: 0,
+        };
+        write!(
+            line,
+            "[{} {}]: {}",
+            record.level(),
+            record.target(),
+            record.args()
+        )
+        .ok();
+        line.bytes[line.len..line.len + 2].copy_from_slice(b"\r\n");
+        line.len += 2;
 
-    #[cortex_m_rt::interrupt]
-    fn USB_OTG1() {
         cortex_m::interrupt::free(|cs| {
-            POLLER
-                .borrow(cs)
-                .borrow_mut()
-                .as_mut()
-                .map(|poller| poller.poll());
+            BUFFER.borrow(cs).borrow_mut().push(&line.bytes[..line.len]);
         });
     }
 
+    fn flush(&self) {}
+}
+
+static LOGGER: Logger = Logger;
+
+/// Initialize the logging system, logging records at `max_level` and up
+///
+/// You should only call this once.
+pub fn init(max_level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_logger(&LOGGER)?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+/// Hand buffered log output to `write`, which returns how many bytes it took. Stops when
+/// `write` takes nothing, or the buffer is empty.
+pub fn drain(mut write: impl FnMut(&[u8]) -> usize) {
     cortex_m::interrupt::free(|cs| {
-        *POLLER.borrow(cs).borrow_mut() = Some(poller);
-        // Safety: invoked in a critical section that also prepares the ISR
-        // shared memory. ISR memory is ready by the time the ISR runs.
-        unsafe { cortex_m::peripheral::NVIC::unmask(bsp::interrupt::USB_OTG1) };
+        let mut buffer = BUFFER.borrow(cs).borrow_mut();
+        while buffer.len > 0 {
+            // Up to the end of the buffer, and whatever wrapped around on the next pass
+            let end = (buffer.start + buffer.len).min(BUFFER_LEN);
+            let written = write(&buffer.bytes[buffer.start..end]);
+            if written == 0 {
+                break;
+            }
+            buffer.start = (buffer.start + written) % BUFFER_LEN;
+            buffer.len -= written;
+        }
     });
 }
@@ -2,18 +2,43 @@
 #![no_main]
 #![feature(never_type)]
 
+mod calibration;
+mod clock;
 mod controller;
+mod flash;
 mod logging;
+mod spi;
+mod storage;
+mod usb;
 
+use bsp::hal::ccm::perclk::{CLKSEL, PODF};
 use bsp::hal::ccm::spi::{ClockSelect, PrescalarSelect};
+use bsp::hal::gpio::GPIO;
+use clock::Clock;
 use controller::Controller;
+use core::cell::RefCell;
+use core::time::Duration;
+use lsm6ds33::config::ctrl1xl::{BwXl, Ctrl1Xl, FsXl, OdrXl};
+use lsm6ds33::config::ctrl2g::{Ctrl2G, Fs125, FsG, OdrG};
 use lsm6ds33::Lsm6ds33;
+use storage::{Slot, Storage};
 use teensy4_bsp as bsp;
 use teensy4_panic as _;
+use tm035035::config::feedconfig1::DataMode;
+use tm035035::{GlidePointError, Tm035035};
+use trackpad::pad::{Pad, PadConfig};
+use trackpad::touches::Touches;
+use trackpad::transform::Transform;
+
+/// Only log records at this level and up. `Trace` logs every frame.
+const LOG_LEVEL: log::LevelFilter = log::LevelFilter::Info;
+
+/// How long after power-on the right pad is watched for a touch picking the USB mode
+const MODE_SELECT_TIME: Duration = Duration::from_millis(100);
 
 #[cortex_m_rt::entry]
 fn main() -> ! {
-    logging::init().unwrap();
+    logging::init(LOG_LEVEL).unwrap();
     let c_peripherals = cortex_m::Peripherals::take().unwrap();
     let mut systick = bsp::SysTick::new(c_peripherals.SYST);
     systick.delay(500);
@@ -30,21 +55,126 @@ fn main() -> ! {
         PrescalarSelect::LPSPI_PODF_7,
     );
 
+    // SPI4 is the only bus broken out, so the IMU and both pads share it
+    let bus: &'static spi::Bus = cortex_m::singleton!(
+        : spi::Bus = RefCell::new(spi4_builder.build(pins.p11, pins.p12, pins.p13))
+    )
+    .unwrap();
+
     // Create the LSM6DS33 driver
     let imu = {
-        let mut cs = bsp::hal::gpio::GPIO::new(pins.p10).output();
-        cs.set();
-        let mut sixaxis_spi = spi4_builder.build(pins.p11, pins.p12, pins.p13);
-        sixaxis_spi.set_mode(embedded_hal::spi::MODE_3).unwrap();
+        let cs = GPIO::new(pins.p10).output();
+        let sixaxis_spi = spi::Device::new(bus, cs, embedded_hal::spi::MODE_3);
         systick.delay(1);
-        Lsm6ds33::try_new(sixaxis_spi, cs)
+        // Fast and wide enough for motion controls
+        let accel = Ctrl1Xl(OdrXl::DataRate208Hz, FsXl::EightG, BwXl::Bw100Hz);
+        let gyro = Ctrl2G(OdrG::DataRate208Hz, FsG::Dps2000, Fs125::DpsByFsG);
+        Lsm6ds33::try_new(sixaxis_spi, spi::Selected, accel, gyro)
             .unwrap_or_else(|_| panic!("Failed to create LSM6DS33 driver"))
     };
 
-    let controller = Controller::new(imu);
+    // Create the trackpad drivers. A pad that doesn't answer is left out, and reads as untouched
+    let mut left_driver = {
+        let cs = GPIO::new(pins.p0).output();
+        let data_ready = GPIO::new(pins.p1);
+        let pad_spi = spi::Device::new(bus, cs, embedded_hal::spi::MODE_1);
+        match Tm035035::try_new(pad_spi, data_ready, DataMode::Absolute, &mut systick, 100) {
+            Ok(driver) => Some(driver),
+            Err(GlidePointError::NotResponding) => {
+                log::warn!("Left trackpad not responding");
+                None
+            }
+            Err(_) => panic!("Failed to create left TM035035 driver"),
+        }
+    };
+    let mut right_driver = {
+        let cs = GPIO::new(pins.p2).output();
+        let data_ready = GPIO::new(pins.p3);
+        let pad_spi = spi::Device::new(bus, cs, embedded_hal::spi::MODE_1);
+        match Tm035035::try_new(pad_spi, data_ready, DataMode::Absolute, &mut systick, 100) {
+            Ok(driver) => Some(driver),
+            Err(GlidePointError::NotResponding) => {
+                log::warn!("Right trackpad not responding");
+                None
+            }
+            Err(_) => panic!("Failed to create right TM035035 driver"),
+        }
+    };
+
+    // Use the stored pad calibrations, unless the left pad is held at power-on to redo them
+    let mut storage = Storage::new();
+    if let Some(pad) = &mut left_driver {
+        calibration::restore(pad, &storage, Slot::LeftPadCalibration);
+    }
+    if let Some(pad) = &mut right_driver {
+        calibration::restore(pad, &storage, Slot::RightPadCalibration);
+    }
+    let recalibrate = left_driver
+        .as_mut()
+        .is_some_and(|pad| calibration::requested(pad, &mut systick));
+    if recalibrate {
+        if let Some(pad) = &mut left_driver {
+            calibration::calibrate(
+                "left",
+                pad,
+                &mut storage,
+                Slot::LeftPadCalibration,
+                &mut systick,
+            );
+        }
+        if let Some(pad) = &mut right_driver {
+            calibration::calibrate(
+                "right",
+                pad,
+                &mut storage,
+                Slot::RightPadCalibration,
+                &mut systick,
+            );
+        }
+    }
+
+    // Input timestamps count from here
+    let clock = {
+        let mut perclk = peripherals.ccm.perclk.configure(
+            &mut peripherals.ccm.handle,
+            PODF::DIVIDE_24,
+            CLKSEL::OSC,
+        );
+        Clock::new(peripherals.gpt1.clock(&mut perclk))
+    };
+
+    // The pads are angled in toward the thumbs, mirror images of each other
+    let left_transform = Transform::new(15f32.to_radians(), false, false, false);
+    let right_transform = left_transform.mirrored();
+
+    let left_pad = Pad::new(
+        Touches::new(left_driver),
+        PadConfig {
+            transform: left_transform,
+            ..Default::default()
+        },
+    );
+    let right_pad = Pad::new(
+        Touches::new(right_driver),
+        PadConfig {
+            transform: right_transform,
+            ..Default::default()
+        },
+    );
+
+    let mut controller = Controller::new(imu, left_pad, right_pad, clock);
+
+    // Touching the right pad at power-on picks the USB mode
+    let mode = controller
+        .by_ref()
+        .take_while(|frame| frame.time < MODE_SELECT_TIME)
+        .find_map(|frame| usb::Mode::select(&frame))
+        .unwrap_or_default();
+    usb::init(mode);
 
     for frame in controller {
-        log::info!("{}", frame);
+        log::trace!("{}", frame);
+        usb::send(&frame);
     }
 
     log::info!("Iterator ended, halting");
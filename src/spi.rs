@@ -0,0 +1,57 @@
+//! SPI4 is the only SPI bus broken out on the Teensy 4.1, so the IMU and both pads share it.
+//! Each device gets its own chip select and SPI mode, applied around every transfer.
+
+use core::cell::RefCell;
+use core::convert::Infallible;
+
+use bsp::hal::iomuxc::consts::U4;
+use bsp::hal::spi::{Error, SPI};
+use embedded_hal::blocking::spi::Transfer;
+use embedded_hal::digital::v2::OutputPin;
+use embedded_hal::spi::Mode;
+use teensy4_bsp as bsp;
+
+pub type Bus = RefCell<SPI<U4>>;
+
+/// One device on the shared bus
+pub struct Device<CS> {
+    bus: &'static Bus,
+    cs: CS,
+    mode: Mode,
+}
+
+impl<CS: OutputPin<Error = Infallible>> Device<CS> {
+    pub fn new(bus: &'static Bus, mut cs: CS, mode: Mode) -> Self {
+        cs.set_high().unwrap();
+        Device { bus, cs, mode }
+    }
+}
+
+impl<CS: OutputPin<Error = Infallible>> Transfer<u8> for Device<CS> {
+    type Error = Error;
+
+    fn transfer<'w>(&mut self, words: &'w mut [u8]) -> Result<&'w [u8], Self::Error> {
+        let mut bus = self.bus.borrow_mut();
+        // Before selecting the device, so it doesn't see the clock change polarity
+        bus.set_mode(self.mode).unwrap();
+        self.cs.set_low().unwrap();
+        let result = bus.transfer(words);
+        self.cs.set_high().unwrap();
+        result
+    }
+}
+
+/// A chip select for drivers that want to drive their own, when the [`Device`] already does
+pub struct Selected;
+
+impl OutputPin for Selected {
+    type Error = Infallible;
+
+    fn set_low(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn set_high(&mut self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+//! Settings kept across power cycles, in EEPROM emulated on the last sectors of flash
+
+use eeprom::Eeprom;
+
+use crate::flash::RomFlash;
+
+/// Where each setting lives
+#[derive(Clone, Copy)]
+pub enum Slot {
+    LeftPadCalibration,
+    RightPadCalibration,
+}
+
+impl Slot {
+    fn id(self) -> u8 {
+        match self {
+            Slot::LeftPadCalibration => 0,
+            Slot::RightPadCalibration => 1,
+        }
+    }
+}
+
+pub struct Storage {
+    eeprom: Eeprom<RomFlash>,
+}
+
+impl Storage {
+    pub fn new() -> Self {
+        Storage {
+            eeprom: Eeprom::new(RomFlash::new()),
+        }
+    }
+
+    /// Fill `buffer` with the setting, if one of that length was stored
+    pub fn read(&self, slot: Slot, buffer: &mut [u8]) -> bool {
+        self.eeprom.read(slot.id(), buffer)
+    }
+
+    /// Store the setting. Failures are logged, and leave the setting as it was.
+    pub fn write(&mut self, slot: Slot, data: &[u8]) {
+        if let Err(error) = self.eeprom.write(slot.id(), data) {
+            log::error!("Failed to store setting {}: {:?}", slot.id(), error);
+        }
+    }
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
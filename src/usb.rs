@@ -0,0 +1,405 @@
+//! USB device support: a `usb-device` stack on USB1, polled from the USB interrupt
+//!
+//! In HID mode, the controller is a composite device: a gamepad, a mouse, a keyboard with
+//! consumer control, and a serial port carrying the log. The other modes are only the controller
+//! they emulate, since their hosts expect exactly its interfaces.
+
+mod ds4;
+mod hid;
+mod hid_gamepad;
+mod hid_keyboard;
+mod hid_mouse;
+mod steam;
+mod switch;
+mod xinput;
+
+use core::cell::RefCell;
+use cortex_m::interrupt::Mutex;
+
+use bsp::hal::ral;
+use bsp::interrupt;
+use teensy4_bsp as bsp;
+use usb_device::bus::UsbBusAllocator;
+use usb_device::device::{UsbDevice, UsbDeviceBuilder, UsbVidPid};
+use usbd_serial::SerialPort;
+
+use crate::controller::Frame;
+use crate::logging;
+use ds4::Ds4;
+use hid::Hid;
+use hid_keyboard::Keyboard;
+use hid_mouse::Mouse;
+use steam::Steam;
+use switch::Switch;
+use xinput::XInput;
+
+type Bus = imxrt_usbd::full_speed::BusAdapter;
+
+/// pid.codes test VID and PID, until we have our own
+const VID_PID: UsbVidPid = UsbVidPid(0x1209, 0x0001);
+
+/// How often the host polls for reports
+const POLL_MS: u8 = 1;
+
+/// Switch hosts expect reports at the Pro Controller's pace
+const SWITCH_POLL_MS: u8 = 8;
+
+/// Likewise for the DualShock 4
+const DS4_POLL_MS: u8 = 4;
+
+/// And for the Steam Controller
+const STEAM_POLL_MS: u8 = 4;
+
+/// USB1's register blocks, handed to `imxrt-usbd`
+struct Usb1 {
+    usb: ral::usb::Instance,
+    usbphy: ral::usbphy::Instance,
+}
+
+unsafe impl imxrt_usbd::Peripherals for Usb1 {
+    fn usb(&self) -> *const () {
+        &*self.usb as *const ral::usb::RegisterBlock as *const ()
+    }
+
+    fn usbphy(&self) -> *const () {
+        &*self.usbphy as *const ral::usbphy::RegisterBlock as *const ()
+    }
+}
+
+/// Touches closer to the centre of the pad than this pick the HID gamepad in [`Mode::select`]
+const SELECT_CENTER_RADIUS: f32 = 0.5;
+
+/// Which protocol the controller speaks. It's chosen at power-on, since hosts don't expect a
+/// device to change what it is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// A standard HID gamepad, with a mouse, keyboard and serial log alongside
+    Hid,
+
+    /// An Xbox 360 controller
+    XInput,
+
+    /// A Switch Pro Controller
+    Switch,
+
+    /// A DualShock 4
+    Ds4,
+
+    /// A wired Steam Controller
+    Steam,
+}
+
+impl Mode {
+    /// Pick a mode from where the right pad is touched while the controller starts:
+    ///
+    /// - the west edge: XInput
+    /// - the east edge: Switch Pro Controller
+    /// - the south edge: DualShock 4
+    /// - the north edge: Steam Controller
+    /// - the centre: HID gamepad
+    ///
+    /// Returns `None` if the right pad isn't touched, which also means a HID gamepad.
+    pub fn select(frame: &Frame) -> Option<Self> {
+        if !frame.right_pad_touched {
+            return None;
+        }
+
+        let (x, y) = (frame.right_pad_x, frame.right_pad_y);
+        Some(
+            if x * x + y * y < SELECT_CENTER_RADIUS * SELECT_CENTER_RADIUS {
+                Mode::Hid
+            } else if x.abs() > y.abs() {
+                if x < 0.0 {
+                    Mode::XInput
+                } else {
+                    Mode::Switch
+                }
+            } else if y > 0.0 {
+                Mode::Ds4
+            } else {
+                Mode::Steam
+            },
+        )
+    }
+}
+
+impl Default for Mode {
+    fn default() -> Self {
+        Mode::Hid
+    }
+}
+
+/// HID mode's interfaces
+struct Composite {
+    gamepad: Hid<'static, Bus>,
+    mouse: Hid<'static, Bus, Mouse>,
+    keyboard: Hid<'static, Bus, Keyboard>,
+    serial: SerialPort<'static, Bus>,
+}
+
+enum Class {
+    Hid(Composite),
+    XInput(XInput<'static, Bus>),
+    Switch(Hid<'static, Bus, Switch>),
+    Ds4(Hid<'static, Bus, Ds4>),
+    Steam(Hid<'static, Bus, Steam>),
+}
+
+struct Device {
+    device: UsbDevice<'static, Bus>,
+    class: Class,
+}
+
+static DEVICE: Mutex<RefCell<Option<Device>>> = Mutex::new(RefCell::new(None));
+
+/// Start the USB PLL and ungate the USB clock. The BSP's logger used to do this for us.
+fn enable_clocks() {
+    // Safety: the HAL owns the CCM, but doesn't touch the USB PLL or the USB clock gate
+    let ccm_analog = unsafe { ral::ccm_analog::CCM_ANALOG::steal() };
+    let ccm = unsafe { ral::ccm::CCM::steal() };
+
+    ral::modify_reg!(ral::ccm_analog, ccm_analog, PLL_USB1, ENABLE: 1, POWER: 1, EN_USB_CLKS: 1);
+    while ral::read_reg!(ral::ccm_analog, ccm_analog, PLL_USB1, LOCK == 0) {}
+    ral::modify_reg!(ral::ccm_analog, ccm_analog, PLL_USB1, BYPASS: 0);
+    ral::modify_reg!(ral::ccm, ccm, CCGR6, CG0: 0b11);
+}
+
+/// Bring up the USB device in `mode`, and prepare the USB ISR to poll it
+///
+/// When `init` returns, the USB interrupt is enabled and the host may enumerate the device.
+/// You should only call this once.
+///
+/// # Panics
+///
+/// Panics if the imxrt-ral USB1 or USBPHY1 instances are already taken.
+pub fn init(mode: Mode) {
+    enable_clocks();
+
+    let usb1 = Usb1 {
+        usb: ral::usb::USB1::take().unwrap(),
+        usbphy: ral::usbphy::USBPHY1::take().unwrap(),
+    };
+    let endpoint_memory = cortex_m::singleton!(: [u8; 2048] = [0; 2048]).unwrap();
+    let mut bus = Bus::new(usb1, endpoint_memory);
+    bus.set_interrupts(true);
+    let allocator: &'static UsbBusAllocator<Bus> =
+        cortex_m::singleton!(: UsbBusAllocator<Bus> = UsbBusAllocator::new(bus)).unwrap();
+
+    let device = match mode {
+        Mode::Hid => {
+            let class = Class::Hid(Composite {
+                gamepad: Hid::new(allocator, gamepad::hid::REPORT_DESCRIPTOR, POLL_MS, ()),
+                mouse: Hid::new(
+                    allocator,
+                    gamepad::mouse::REPORT_DESCRIPTOR,
+                    POLL_MS,
+                    Mouse::new(),
+                ),
+                keyboard: Hid::new(
+                    allocator,
+                    gamepad::keyboard::REPORT_DESCRIPTOR,
+                    POLL_MS,
+                    Keyboard::new(),
+                ),
+                serial: SerialPort::new(allocator),
+            });
+            // The serial port's two interfaces need an association descriptor to stay together
+            let device = UsbDeviceBuilder::new(allocator, VID_PID)
+                .manufacturer("Alex Peters")
+                .product("cfw")
+                .composite_with_iads()
+                .max_packet_size_0(64)
+                .build();
+            Device { device, class }
+        }
+        Mode::XInput => {
+            let class = Class::XInput(XInput::new(allocator));
+            let (class_code, sub_class, protocol) = gamepad::xinput::DEVICE_CLASS;
+            let device = UsbDeviceBuilder::new(
+                allocator,
+                UsbVidPid(gamepad::xinput::VID, gamepad::xinput::PID),
+            )
+            .manufacturer("©Microsoft Corporation")
+            .product("Controller")
+            .device_class(class_code)
+            .device_sub_class(sub_class)
+            .device_protocol(protocol)
+            .device_release(gamepad::xinput::DEVICE_RELEASE)
+            .max_packet_size_0(8)
+            .build();
+            Device { device, class }
+        }
+        Mode::Switch => {
+            let class = Class::Switch(Hid::with_output(
+                allocator,
+                gamepad::switch::REPORT_DESCRIPTOR,
+                SWITCH_POLL_MS,
+                Switch::new(),
+            ));
+            let device = UsbDeviceBuilder::new(
+                allocator,
+                UsbVidPid(gamepad::switch::VID, gamepad::switch::PID),
+            )
+            .manufacturer("Nintendo Co., Ltd.")
+            .product("Pro Controller")
+            .serial_number("000000000001")
+            .device_release(0x0200)
+            .max_packet_size_0(64)
+            .build();
+            Device { device, class }
+        }
+        Mode::Ds4 => {
+            let class = Class::Ds4(Hid::with_output(
+                allocator,
+                gamepad::ds4::REPORT_DESCRIPTOR,
+                DS4_POLL_MS,
+                Ds4::new(),
+            ));
+            let device =
+                UsbDeviceBuilder::new(allocator, UsbVidPid(gamepad::ds4::VID, gamepad::ds4::PID))
+                    .manufacturer("Sony Computer Entertainment")
+                    .product("Wireless Controller")
+                    .device_release(0x0100)
+                    .max_packet_size_0(64)
+                    .build();
+            Device { device, class }
+        }
+        Mode::Steam => {
+            let class = Class::Steam(Hid::new(
+                allocator,
+                gamepad::steam::REPORT_DESCRIPTOR,
+                STEAM_POLL_MS,
+                Steam::new(),
+            ));
+            let device = UsbDeviceBuilder::new(
+                allocator,
+                UsbVidPid(gamepad::steam::VID, gamepad::steam::PID),
+            )
+            .manufacturer("Valve Software")
+            .product("Steam Controller")
+            .serial_number(steam::SERIAL)
+            .device_release(0x0001)
+            .max_packet_size_0(64)
+            .build();
+            Device { device, class }
+        }
+    };
+
+    setup(device);
+}
+
+/// Setup the USB ISR to poll the device
+fn setup(device: Device) {
+    #[cortex_m_rt::interrupt]
+    fn USB_OTG1() {
+        cortex_m::interrupt::free(|cs| {
+            if let Some(Device { device, class }) = DEVICE.borrow(cs).borrow_mut().as_mut() {
+                match class {
+                    Class::Hid(composite) => {
+                        let Composite {
+                            gamepad,
+                            mouse,
+                            keyboard,
+                            serial,
+                        } = composite;
+                        device.poll(&mut [gamepad, mouse, keyboard, serial]);
+                        drain_log(serial);
+                    }
+                    Class::XInput(xinput) => {
+                        device.poll(&mut [xinput]);
+                    }
+                    Class::Switch(switch) => {
+                        device.poll(&mut [switch]);
+                    }
+                    Class::Ds4(ds4) => {
+                        device.poll(&mut [ds4]);
+                    }
+                    Class::Steam(steam) => {
+                        device.poll(&mut [steam]);
+                    }
+                }
+            }
+        });
+    }
+
+    cortex_m::interrupt::free(|cs| {
+        *DEVICE.borrow(cs).borrow_mut() = Some(device);
+        // Safety: invoked in a critical section that also prepares the ISR
+        // shared memory. ISR memory is ready by the time the ISR runs.
+        unsafe { cortex_m::peripheral::NVIC::unmask(bsp::interrupt::USB_OTG1) };
+    });
+}
+
+/// Offer a frame to the host, in whichever protocol it's speaking. Until the host takes it, any
+/// more frames are dropped.
+pub fn send(frame: &Frame) {
+    cortex_m::interrupt::free(|cs| {
+        let mut device = DEVICE.borrow(cs).borrow_mut();
+        let class = match device.as_mut() {
+            Some(Device { class, .. }) => class,
+            None => return,
+        };
+        match class {
+            Class::Hid(composite) => {
+                let Composite {
+                    gamepad,
+                    mouse,
+                    keyboard,
+                    serial,
+                } = composite;
+                gamepad.write(&hid_gamepad::report(frame).to_bytes());
+
+                if let Some(report) = mouse.protocol().report(frame) {
+                    if mouse.write(&report.to_bytes()) {
+                        mouse.protocol().sent();
+                    }
+                }
+
+                // Keys and media keys share an endpoint, so either may wait for the next frame
+                let keys = hid_keyboard::keyboard_report(frame);
+                if keys != keyboard.protocol().keys && keyboard.write(&keys.to_bytes()) {
+                    keyboard.protocol().keys = keys;
+                }
+                let media = hid_keyboard::consumer_report(frame);
+                if media != keyboard.protocol().media && keyboard.write(&media.to_bytes()) {
+                    keyboard.protocol().media = media;
+                }
+
+                drain_log(serial);
+            }
+            Class::XInput(xinput) => {
+                xinput.write(&gamepad::xinput::report(frame));
+            }
+            Class::Switch(switch) => {
+                if let Some(report) = switch.protocol().report(frame) {
+                    if switch.write(&report) {
+                        switch.protocol().sent();
+                    }
+                }
+            }
+            Class::Ds4(ds4) => {
+                let report = ds4.protocol().report(frame);
+                if ds4.write(&report.to_bytes()) {
+                    ds4.protocol().sent();
+                }
+            }
+            Class::Steam(steam) => {
+                let report = steam.protocol().report(frame);
+                if steam.write(&report.to_bytes()) {
+                    steam.protocol().sent();
+                }
+            }
+        }
+    });
+}
+
+/// Send what the serial port will take of the log, and throw away anything the host typed
+fn drain_log(serial: &mut SerialPort<'static, Bus>) {
+    let mut discard = [0; 64];
+    while let Ok(len) = serial.read(&mut discard) {
+        if len == 0 {
+            break;
+        }
+    }
+    logging::drain(|bytes| serial.write(bytes).unwrap_or(0));
+}
@@ -0,0 +1,44 @@
+//! The DualShock 4 protocol
+
+use gamepad::ds4::{self, FrameMapping, InputReport, Output};
+
+use crate::controller::Frame;
+use crate::usb::hid::Protocol;
+
+/// A locally administered address, since there's no Bluetooth radio to take one from
+const MAC: [u8; 6] = [0x02, 0x63, 0x66, 0x77, 0x00, 0x02];
+
+pub struct Ds4 {
+    mapping: FrameMapping,
+}
+
+impl Ds4 {
+    pub fn new() -> Self {
+        Ds4 {
+            mapping: FrameMapping::new(),
+        }
+    }
+
+    pub fn report(&mut self, frame: &Frame) -> InputReport {
+        self.mapping.report(frame)
+    }
+
+    /// The last report from `report` was sent
+    pub fn sent(&mut self) {
+        self.mapping.sent();
+    }
+}
+
+impl Protocol for Ds4 {
+    fn output(&mut self, report: &[u8]) {
+        // There are no motors or lights to drive, so outputs are only logged
+        match Output::parse(report) {
+            Some(output) => log::debug!("DS4 output: {:?}", output),
+            None => log::debug!("Unknown DS4 output: {:02x?}", report),
+        }
+    }
+
+    fn get_feature(&mut self, id: u8, report: &mut [u8]) -> Option<usize> {
+        ds4::feature_report(id, MAC, report)
+    }
+}
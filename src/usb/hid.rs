@@ -0,0 +1,179 @@
+//! A HID interface with an interrupt IN endpoint, and optionally an interrupt OUT endpoint
+
+use usb_device::class_prelude::*;
+use usb_device::control::{Recipient, Request, RequestType};
+
+const HID_DESCRIPTOR: u8 = 0x21;
+const REPORT_DESCRIPTOR: u8 = 0x22;
+
+const GET_REPORT: u8 = 0x01;
+const SET_REPORT: u8 = 0x09;
+const SET_IDLE: u8 = 0x0a;
+
+const OUTPUT_REPORT: u8 = 0x02;
+const FEATURE_REPORT: u8 = 0x03;
+
+const MAX_REPORT_LEN: usize = 64;
+
+/// What a HID protocol does with reports from the host. Reports start with their report ID, if
+/// the protocol uses them.
+pub trait Protocol {
+    /// An output report arrived, on the OUT endpoint or by SET_REPORT
+    fn output(&mut self, _report: &[u8]) {}
+
+    /// The host sent a feature report
+    fn set_feature(&mut self, _report: &[u8]) {}
+
+    /// The host asked for feature report `id`. Returns its length, or `None` to stall.
+    fn get_feature(&mut self, _id: u8, _report: &mut [u8]) -> Option<usize> {
+        None
+    }
+}
+
+/// For protocols that only send input reports
+impl Protocol for () {}
+
+pub struct Hid<'a, B: UsbBus, P: Protocol = ()> {
+    interface: InterfaceNumber,
+    endpoint_in: EndpointIn<'a, B>,
+    endpoint_out: Option<EndpointOut<'a, B>>,
+    report_descriptor: &'static [u8],
+    last_report: [u8; MAX_REPORT_LEN],
+    last_report_len: usize,
+    protocol: P,
+}
+
+impl<'a, B: UsbBus, P: Protocol> Hid<'a, B, P> {
+    /// The host polls for reports every `poll_ms` milliseconds
+    pub fn new(
+        allocator: &'a UsbBusAllocator<B>,
+        report_descriptor: &'static [u8],
+        poll_ms: u8,
+        protocol: P,
+    ) -> Self {
+        Hid {
+            interface: allocator.interface(),
+            endpoint_in: allocator.interrupt(MAX_REPORT_LEN as u16, poll_ms),
+            endpoint_out: None,
+            report_descriptor,
+            last_report: [0; MAX_REPORT_LEN],
+            last_report_len: 0,
+            protocol,
+        }
+    }
+
+    /// Like `new`, with an OUT endpoint the host can send output reports on
+    pub fn with_output(
+        allocator: &'a UsbBusAllocator<B>,
+        report_descriptor: &'static [u8],
+        poll_ms: u8,
+        protocol: P,
+    ) -> Self {
+        let mut hid = Self::new(allocator, report_descriptor, poll_ms, protocol);
+        hid.endpoint_out = Some(allocator.interrupt(MAX_REPORT_LEN as u16, poll_ms));
+        hid
+    }
+
+    pub fn protocol(&mut self) -> &mut P {
+        &mut self.protocol
+    }
+
+    /// Queue an input report for the next poll. Returns false if the host hasn't taken the last
+    /// one yet, so the report was dropped.
+    pub fn write(&mut self, report: &[u8]) -> bool {
+        match self.endpoint_in.write(report) {
+            Ok(len) => {
+                self.last_report[..len].copy_from_slice(&report[..len]);
+                self.last_report_len = len;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn is_ours(&self, request: &Request) -> bool {
+        request.recipient == Recipient::Interface
+            && request.index == u8::from(self.interface) as u16
+    }
+}
+
+impl<B: UsbBus, P: Protocol> UsbClass<B> for Hid<'_, B, P> {
+    fn get_configuration_descriptors(
+        &self,
+        writer: &mut DescriptorWriter,
+    ) -> usb_device::Result<()> {
+        writer.interface(self.interface, 0x03, 0x00, 0x00)?;
+        let [len_low, len_high] = (self.report_descriptor.len() as u16).to_le_bytes();
+        // HID 1.11, no country, one report descriptor
+        writer.write(
+            HID_DESCRIPTOR,
+            &[0x11, 0x01, 0x00, 0x01, REPORT_DESCRIPTOR, len_low, len_high],
+        )?;
+        writer.endpoint(&self.endpoint_in)?;
+        if let Some(endpoint_out) = &self.endpoint_out {
+            writer.endpoint(endpoint_out)?;
+        }
+        Ok(())
+    }
+
+    fn control_in(&mut self, xfer: ControlIn<B>) {
+        let request = *xfer.request();
+        if !self.is_ours(&request) {
+            return;
+        }
+        let [report_id, report_type] = request.value.to_le_bytes();
+        let result = match (request.request_type, request.request) {
+            (RequestType::Standard, Request::GET_DESCRIPTOR) => {
+                match request.descriptor_type_index() {
+                    (REPORT_DESCRIPTOR, _) => xfer.accept_with_static(self.report_descriptor),
+                    _ => xfer.reject(),
+                }
+            }
+            (RequestType::Class, GET_REPORT) if report_type == FEATURE_REPORT => {
+                let mut report = [0; MAX_REPORT_LEN];
+                match self.protocol.get_feature(report_id, &mut report) {
+                    Some(len) => xfer.accept_with(&report[..len]),
+                    None => xfer.reject(),
+                }
+            }
+            (RequestType::Class, GET_REPORT) => {
+                xfer.accept_with(&self.last_report[..self.last_report_len])
+            }
+            _ => return,
+        };
+        result.ok();
+    }
+
+    fn control_out(&mut self, xfer: ControlOut<B>) {
+        let request = *xfer.request();
+        if !self.is_ours(&request) {
+            return;
+        }
+        let [_, report_type] = request.value.to_le_bytes();
+        let result = match (request.request_type, request.request) {
+            // Reports only go out when they're written, so there's nothing to idle
+            (RequestType::Class, SET_IDLE) => xfer.accept(),
+            (RequestType::Class, SET_REPORT) if report_type == OUTPUT_REPORT => {
+                self.protocol.output(xfer.data());
+                xfer.accept()
+            }
+            (RequestType::Class, SET_REPORT) if report_type == FEATURE_REPORT => {
+                self.protocol.set_feature(xfer.data());
+                xfer.accept()
+            }
+            _ => return,
+        };
+        result.ok();
+    }
+
+    fn endpoint_out(&mut self, address: EndpointAddress) {
+        let endpoint_out = match &self.endpoint_out {
+            Some(endpoint_out) if endpoint_out.address() == address => endpoint_out,
+            _ => return,
+        };
+        let mut report = [0; MAX_REPORT_LEN];
+        if let Ok(len) = endpoint_out.read(&mut report) {
+            self.protocol.output(&report[..len]);
+        }
+    }
+}
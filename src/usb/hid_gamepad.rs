@@ -0,0 +1,87 @@
+//! Maps `Frame` onto the standard HID gamepad
+//!
+//! Buttons are numbered the way Linux maps them for gamepads, so the right quad lands on
+//! south/east/north/west, the bumpers on TL/TR and so on. Gesture buttons follow from button 17,
+//! then radial menu segments from button 33, eight per pad. The scroll wheel is left to the mouse.
+
+use gamepad::hid::{self, GamepadReport};
+use gamepad::{axis, hat, trigger};
+
+use crate::controller::Frame;
+
+/// Radial menu segments per pad that get buttons
+const RADIAL_SEGMENTS: u8 = 8;
+
+pub fn report(frame: &Frame) -> GamepadReport {
+    let imu = |value: f32, per_unit: f32| (value * per_unit) as i16;
+    let mut report = GamepadReport {
+        hat: hat(
+            frame.left_quad_n,
+            frame.left_quad_e,
+            frame.left_quad_s,
+            frame.left_quad_w,
+        ),
+        left_stick: (axis(frame.left_stick_x), axis(frame.left_stick_y)),
+        right_stick: (axis(frame.right_stick_x), axis(frame.right_stick_y)),
+        left_pad: (axis(frame.left_pad_x), axis(frame.left_pad_y)),
+        right_pad: (axis(frame.right_pad_x), axis(frame.right_pad_y)),
+        left_trigger: trigger(frame.left_trigger),
+        right_trigger: trigger(frame.right_trigger),
+        accel: (
+            imu(frame.accel_x, hid::ACCEL_PER_G),
+            imu(frame.accel_y, hid::ACCEL_PER_G),
+            imu(frame.accel_z, hid::ACCEL_PER_G),
+        ),
+        gyro: (
+            imu(frame.gyro_x, hid::GYRO_PER_DPS),
+            imu(frame.gyro_y, hid::GYRO_PER_DPS),
+            imu(frame.gyro_z, hid::GYRO_PER_DPS),
+        ),
+        ..Default::default()
+    };
+
+    let buttons = [
+        (1, frame.right_quad_s),
+        (2, frame.right_quad_e),
+        (4, frame.right_quad_n),
+        (5, frame.right_quad_w),
+        (7, frame.left_bumper),
+        (8, frame.right_bumper),
+        (11, frame.select),
+        (12, frame.start),
+        (13, frame.home),
+        (14, frame.left_pad_click),
+        (15, frame.right_pad_click),
+        (17, frame.left_grip),
+        (18, frame.right_grip),
+        (19, frame.left_swipe_n),
+        (20, frame.left_swipe_e),
+        (21, frame.left_swipe_s),
+        (22, frame.left_swipe_w),
+        (23, frame.right_swipe_n),
+        (24, frame.right_swipe_e),
+        (25, frame.right_swipe_s),
+        (26, frame.right_swipe_w),
+        (27, frame.left_tap),
+        (28, frame.left_double_tap),
+        (29, frame.right_tap),
+        (30, frame.right_double_tap),
+        (31, frame.left_radial_center),
+        (32, frame.right_radial_center),
+    ];
+    for &(button, pressed) in buttons.iter() {
+        report.set_button(button, pressed);
+    }
+
+    let segments = [
+        (33, frame.left_radial_segment),
+        (33 + RADIAL_SEGMENTS, frame.right_radial_segment),
+    ];
+    for &(first, segment) in segments.iter() {
+        if let Some(segment) = segment.filter(|&segment| segment < RADIAL_SEGMENTS) {
+            report.set_button(first + segment, true);
+        }
+    }
+
+    report
+}
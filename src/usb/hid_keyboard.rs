@@ -0,0 +1,75 @@
+//! Maps `Frame` onto the HID keyboard and consumer control
+//!
+//! Radial menus are for shortcuts. The right pad's segments press F13 onward, which nothing else
+//! uses, so they can be bound to anything on the host. The left pad's first four segments are
+//! media keys, going clockwise from north: volume up, next track, volume down, previous track.
+
+use gamepad::keyboard::{consumer, key, ConsumerReport, KeyboardReport};
+
+use crate::controller::Frame;
+use crate::usb::hid::Protocol;
+
+const KEYS: [u8; 12] = [
+    key::F13,
+    key::F14,
+    key::F15,
+    key::F16,
+    key::F17,
+    key::F18,
+    key::F19,
+    key::F20,
+    key::F21,
+    key::F22,
+    key::F23,
+    key::F24,
+];
+
+const MEDIA: [u16; 4] = [
+    consumer::VOLUME_UP,
+    consumer::NEXT_TRACK,
+    consumer::VOLUME_DOWN,
+    consumer::PREVIOUS_TRACK,
+];
+
+pub fn keyboard_report(frame: &Frame) -> KeyboardReport {
+    let mut report = KeyboardReport::default();
+    if let Some(&key) = frame
+        .right_radial_segment
+        .and_then(|segment| KEYS.get(segment as usize))
+    {
+        report.press(key);
+    }
+    report
+}
+
+pub fn consumer_report(frame: &Frame) -> ConsumerReport {
+    ConsumerReport {
+        usage: frame
+            .left_radial_segment
+            .and_then(|segment| MEDIA.get(segment as usize))
+            .copied()
+            .unwrap_or(0),
+    }
+}
+
+/// The last reports the host took, so they're only sent again when they change
+pub struct Keyboard {
+    pub keys: KeyboardReport,
+    pub media: ConsumerReport,
+}
+
+impl Keyboard {
+    pub fn new() -> Self {
+        Keyboard {
+            keys: KeyboardReport::default(),
+            media: ConsumerReport::default(),
+        }
+    }
+}
+
+impl Protocol for Keyboard {
+    fn output(&mut self, report: &[u8]) {
+        // TODO Nothing to light up yet
+        log::debug!("Keyboard LEDs: {:02x?}", report);
+    }
+}
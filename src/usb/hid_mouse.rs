@@ -0,0 +1,58 @@
+//! Maps `Frame` onto the HID mouse
+//!
+//! The pads move the mouse and turn its wheel, in whichever modes they're configured for that.
+//! Tapping the right pad is a left click, and tapping the left pad a right click.
+
+use gamepad::mouse::{button, MouseReport};
+
+use crate::controller::Frame;
+use crate::usb::hid::Protocol;
+
+/// Motion and wheel ticks the host hasn't taken yet, so none are lost between reports
+pub struct Mouse {
+    pending: MouseReport,
+    last: MouseReport,
+}
+
+impl Mouse {
+    pub fn new() -> Self {
+        Mouse {
+            pending: MouseReport::default(),
+            last: MouseReport::default(),
+        }
+    }
+
+    /// The next report to send, or `None` if there's nothing new to tell the host
+    pub fn report(&mut self, frame: &Frame) -> Option<MouseReport> {
+        let buttons = [
+            (button::LEFT, frame.right_tap),
+            (button::RIGHT, frame.left_tap),
+        ];
+        self.pending.buttons = buttons
+            .iter()
+            .filter(|&&(_, pressed)| pressed)
+            .fold(0, |bits, &(bit, _)| bits | bit);
+        self.pending.x = self.pending.x.saturating_add(frame.mouse_x);
+        self.pending.y = self.pending.y.saturating_add(frame.mouse_y);
+        self.pending.wheel = self.pending.wheel.saturating_add(frame.wheel);
+
+        let moved = self.pending.x != 0 || self.pending.y != 0 || self.pending.wheel != 0;
+        if moved || self.pending.buttons != self.last.buttons {
+            Some(self.pending)
+        } else {
+            None
+        }
+    }
+
+    /// The last report from `report` was sent
+    pub fn sent(&mut self) {
+        self.last = self.pending;
+        self.pending = MouseReport {
+            buttons: self.pending.buttons,
+            ..Default::default()
+        };
+    }
+}
+
+/// The mouse only sends input reports
+impl Protocol for Mouse {}
@@ -0,0 +1,116 @@
+//! The Steam Controller protocol, and the mapping from `Frame` onto its reports
+//!
+//! Both pads go to Steam as pads, so Steam Input can configure them like a Steam Controller's.
+//! Buttons map by position, so the south quad button is A.
+
+use gamepad::steam::{self, button, Command, InputReport};
+use gamepad::{axis, trigger, trigger_pressed};
+
+use crate::controller::Frame;
+use crate::usb::hid::Protocol;
+
+/// Serial number, reported to Steam and in the device descriptor
+pub const SERIAL: &str = "CFW0000001";
+
+pub struct Steam {
+    sequence: u32,
+    /// The last command, which says what Steam reads back next
+    command: Command,
+}
+
+impl Steam {
+    pub fn new() -> Self {
+        Steam {
+            sequence: 0,
+            command: Command::Other(0),
+        }
+    }
+
+    pub fn report(&self, frame: &Frame) -> InputReport {
+        let buttons = [
+            (button::A, frame.right_quad_s),
+            (button::B, frame.right_quad_e),
+            (button::X, frame.right_quad_w),
+            (button::Y, frame.right_quad_n),
+            (button::DPAD_UP, frame.left_quad_n),
+            (button::DPAD_RIGHT, frame.left_quad_e),
+            (button::DPAD_DOWN, frame.left_quad_s),
+            (button::DPAD_LEFT, frame.left_quad_w),
+            (button::LEFT_BUMPER, frame.left_bumper),
+            (button::RIGHT_BUMPER, frame.right_bumper),
+            (button::LEFT_TRIGGER, trigger_pressed(frame.left_trigger)),
+            (button::RIGHT_TRIGGER, trigger_pressed(frame.right_trigger)),
+            (button::BACK, frame.select),
+            (button::START, frame.start),
+            (button::STEAM, frame.home),
+            (button::LEFT_GRIP, frame.left_grip),
+            (button::RIGHT_GRIP, frame.right_grip),
+            (button::LEFT_PAD_CLICK, frame.left_pad_click),
+            (button::RIGHT_PAD_CLICK, frame.right_pad_click),
+            (button::LEFT_PAD_TOUCH, frame.left_pad_touched),
+            (button::RIGHT_PAD_TOUCH, frame.right_pad_touched),
+        ];
+
+        // Steam's +Y is up
+        let pad = |touched: bool, x: f32, y: f32| {
+            if touched {
+                (axis(x), axis(-y))
+            } else {
+                (0, 0)
+            }
+        };
+        let imu = |value: f32, per_unit: f32| (value * per_unit) as i16;
+        InputReport {
+            sequence: self.sequence,
+            buttons: buttons
+                .iter()
+                .filter(|&&(_, pressed)| pressed)
+                .fold(0, |bits, &(bit, _)| bits | bit),
+            left_trigger: trigger(frame.left_trigger),
+            right_trigger: trigger(frame.right_trigger),
+            left_pad: pad(frame.left_pad_touched, frame.left_pad_x, frame.left_pad_y),
+            right_pad: pad(
+                frame.right_pad_touched,
+                frame.right_pad_x,
+                frame.right_pad_y,
+            ),
+            accel: (
+                imu(frame.accel_x, steam::ACCEL_PER_G),
+                imu(frame.accel_y, steam::ACCEL_PER_G),
+                imu(frame.accel_z, steam::ACCEL_PER_G),
+            ),
+            gyro: (
+                imu(frame.gyro_x, steam::GYRO_PER_DPS),
+                imu(frame.gyro_y, steam::GYRO_PER_DPS),
+                imu(frame.gyro_z, steam::GYRO_PER_DPS),
+            ),
+        }
+    }
+
+    /// The last report from `report` was sent
+    pub fn sent(&mut self) {
+        self.sequence = self.sequence.wrapping_add(1);
+    }
+}
+
+impl Protocol for Steam {
+    fn set_feature(&mut self, report: &[u8]) {
+        match Command::parse(report) {
+            Some(command) => {
+                // TODO Nothing to pulse yet
+                if let Command::HapticPulse(pulse) = command {
+                    log::debug!("Steam haptic pulse: {:?}", pulse);
+                }
+                self.command = command;
+            }
+            None => log::debug!("Empty Steam feature report"),
+        }
+    }
+
+    fn get_feature(&mut self, _id: u8, report: &mut [u8]) -> Option<usize> {
+        let mut reply = [0; steam::REPORT_LEN];
+        steam::reply(&self.command, SERIAL, &mut reply);
+        report[..reply.len()].copy_from_slice(&reply);
+        Some(reply.len())
+    }
+}
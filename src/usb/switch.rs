@@ -0,0 +1,40 @@
+//! The Switch Pro Controller protocol
+
+use gamepad::switch::{FrameMapping, ProController};
+
+use crate::controller::Frame;
+use crate::usb::hid::Protocol;
+
+/// A locally administered address, since there's no Bluetooth radio to take one from
+const MAC: [u8; 6] = [0x02, 0x63, 0x66, 0x77, 0x00, 0x01];
+
+pub struct Switch {
+    controller: ProController,
+    mapping: FrameMapping,
+}
+
+impl Switch {
+    pub fn new() -> Self {
+        Switch {
+            controller: ProController::new(MAC),
+            mapping: FrameMapping::new(),
+        }
+    }
+
+    /// Take in a frame, and return the report to send for it, if any
+    pub fn report(&mut self, frame: &Frame) -> Option<[u8; gamepad::switch::REPORT_LEN]> {
+        let input = self.mapping.update(frame);
+        self.controller.next_report(input)
+    }
+
+    /// The last report from `report` was sent
+    pub fn sent(&mut self) {
+        self.controller.sent();
+    }
+}
+
+impl Protocol for Switch {
+    fn output(&mut self, report: &[u8]) {
+        self.controller.output(report);
+    }
+}
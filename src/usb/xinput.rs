@@ -0,0 +1,73 @@
+//! The Xbox 360 controller interface
+
+use usb_device::class_prelude::*;
+
+use gamepad::xinput::{self, InputReport, Output};
+
+pub struct XInput<'a, B: UsbBus> {
+    interface: InterfaceNumber,
+    endpoint_in: EndpointIn<'a, B>,
+    endpoint_out: EndpointOut<'a, B>,
+}
+
+impl<'a, B: UsbBus> XInput<'a, B> {
+    /// Hosts expect the controller's endpoints at fixed addresses, so this has to be allocated
+    /// before any other class.
+    pub fn new(allocator: &'a UsbBusAllocator<B>) -> Self {
+        XInput {
+            interface: allocator.interface(),
+            endpoint_in: allocator
+                .alloc(
+                    Some(xinput::IN_ENDPOINT.into()),
+                    EndpointType::Interrupt,
+                    32,
+                    4,
+                )
+                .expect("XInput IN endpoint is taken"),
+            endpoint_out: allocator
+                .alloc(
+                    Some(xinput::OUT_ENDPOINT.into()),
+                    EndpointType::Interrupt,
+                    32,
+                    8,
+                )
+                .expect("XInput OUT endpoint is taken"),
+        }
+    }
+
+    /// Queue an input report for the next poll. Returns false if the host hasn't taken the last
+    /// one yet, so the report was dropped.
+    pub fn write(&mut self, report: &InputReport) -> bool {
+        self.endpoint_in.write(&report.to_bytes()).is_ok()
+    }
+}
+
+impl<B: UsbBus> UsbClass<B> for XInput<'_, B> {
+    fn get_configuration_descriptors(
+        &self,
+        writer: &mut DescriptorWriter,
+    ) -> usb_device::Result<()> {
+        let (class, sub_class, protocol) = xinput::INTERFACE_CLASS;
+        writer.interface(self.interface, class, sub_class, protocol)?;
+        writer.write(
+            xinput::INTERFACE_DESCRIPTOR_TYPE,
+            xinput::INTERFACE_DESCRIPTOR,
+        )?;
+        writer.endpoint(&self.endpoint_in)?;
+        writer.endpoint(&self.endpoint_out)
+    }
+
+    fn endpoint_out(&mut self, address: EndpointAddress) {
+        if address != self.endpoint_out.address() {
+            return;
+        }
+        let mut bytes = [0; 32];
+        if let Ok(len) = self.endpoint_out.read(&mut bytes) {
+            // There are no motors or lights to drive, so outputs are only logged
+            match Output::parse(&bytes[..len]) {
+                Some(output) => log::debug!("XInput output: {:?}", output),
+                None => log::debug!("Unknown XInput output: {:02x?}", &bytes[..len]),
+            }
+        }
+    }
+}